@@ -0,0 +1,62 @@
+//! Compares the bitset operations with and without the explicit SIMD kernels:
+//!
+//! ```text
+//! cargo bench --bench bitset
+//! cargo bench --bench bitset --features simd
+//! ```
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use collections::{words_for_bits, BitSet};
+
+const ITERS: u32 = 100_000;
+
+fn bench<T>(name: &str, mut f: impl FnMut() -> T) {
+    // warm up, and make sure any runtime feature detection has been cached
+    for _ in 0..ITERS / 10 {
+        black_box(f());
+    }
+
+    let start = Instant::now();
+    for _ in 0..ITERS {
+        black_box(f());
+    }
+    let elapsed = start.elapsed();
+
+    println!(
+        "{name:<32} {:>10.1} ns/iter",
+        elapsed.as_nanos() as f64 / ITERS as f64
+    );
+}
+
+fn bench_width<const N: usize, const L: usize>() {
+    let mut a = BitSet::<N, L>::new();
+    let mut b = BitSet::<N, L>::new();
+    for i in (0..N).step_by(3) {
+        a.set(i);
+    }
+    for i in (0..N).step_by(5) {
+        b.set(i);
+    }
+    let union = &a | &b;
+    let empty = BitSet::<N, L>::new();
+
+    println!("BitSet<{N}>");
+    bench("  and", || black_box(&a) & black_box(&b));
+    bench("  or", || black_box(&a) | black_box(&b));
+    bench("  xor", || black_box(&a) ^ black_box(&b));
+    bench("  not", || !black_box(&a));
+    bench("  is_empty", || black_box(&empty).is_empty());
+    bench("  contains", || black_box(&union).contains(black_box(&a)));
+    bench("  count_ones", || black_box(&a).count_ones());
+}
+
+fn main() {
+    println!("simd feature enabled: {}", cfg!(feature = "simd"));
+
+    bench_width::<256, { words_for_bits(256) }>();
+    bench_width::<1024, { words_for_bits(1024) }>();
+    bench_width::<4096, { words_for_bits(4096) }>();
+    bench_width::<65536, { words_for_bits(65536) }>();
+}
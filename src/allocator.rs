@@ -0,0 +1,242 @@
+use core::{alloc::Layout, ptr::NonNull};
+use std::alloc;
+
+/// A source of memory for collections that can use something other than the global allocator,
+/// e.g. an arena. This is a stable stand-in for the unstable `core::alloc::Allocator`.
+///
+/// # Safety
+/// Implementors must ensure that:
+/// - A block returned by `allocate` or `grow` is valid for reads and writes of the size of its
+///   layout, and aligned to its alignment, until it is passed to `deallocate` or `grow`.
+/// - Blocks stay valid if the allocator is moved.
+pub unsafe trait Allocator {
+    /// Returns a new block of memory, or `None` if the allocation fails
+    ///
+    /// # Safety
+    /// The caller must ensure that:
+    /// - The layout has a non-zero size.
+    unsafe fn allocate(&self, layout: Layout) -> Option<NonNull<u8>>;
+
+    /// # Safety
+    /// The caller must ensure that:
+    /// - The block was returned by this allocator, with the given layout.
+    /// - The block is not used after this.
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout);
+
+    /// Returns a bigger block holding the contents of the old one, or `None` if the allocation
+    /// fails, in which case the old block is still valid.
+    ///
+    /// Allocators that can't resize blocks in place can rely on the provided implementation, which
+    /// allocates a new block, copies the contents across and deallocates the old block.
+    ///
+    /// # Safety
+    /// The caller must ensure that:
+    /// - The block was returned by this allocator, with the old layout.
+    /// - The new layout is at least as big as the old one, and has the same alignment.
+    /// - The old block is not used after this, unless `None` is returned.
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Option<NonNull<u8>> {
+        debug_assert!(new_layout.size() >= old_layout.size());
+
+        // SAFETY: Upheld by the caller
+        unsafe { reallocate_by_copying(self, ptr, old_layout, new_layout) }
+    }
+
+    /// Returns a smaller block holding as much of the contents of the old one as fits, or `None`
+    /// if the allocation fails, in which case the old block is still valid.
+    ///
+    /// The provided implementation allocates a new block, copies the contents across and
+    /// deallocates the old block.
+    ///
+    /// # Safety
+    /// The caller must ensure that:
+    /// - The block was returned by this allocator, with the old layout.
+    /// - The new layout is no bigger than the old one, has a non-zero size, and has the same
+    ///   alignment.
+    /// - The old block is not used after this, unless `None` is returned.
+    unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Option<NonNull<u8>> {
+        debug_assert!(new_layout.size() <= old_layout.size());
+
+        // SAFETY: Upheld by the caller
+        unsafe { reallocate_by_copying(self, ptr, old_layout, new_layout) }
+    }
+}
+
+/// # Safety
+/// The same as [Allocator::grow] or [Allocator::shrink]
+unsafe fn reallocate_by_copying<A: Allocator + ?Sized>(
+    allocator: &A,
+    ptr: NonNull<u8>,
+    old_layout: Layout,
+    new_layout: Layout,
+) -> Option<NonNull<u8>> {
+    // SAFETY: The new layout has a non-zero size, as is deferred to the caller
+    let new_ptr = unsafe { allocator.allocate(new_layout)? };
+
+    // SAFETY:
+    // - Both blocks are valid for at least the smaller of the two sizes.
+    // - The new block was only just allocated, so can't overlap the old one.
+    // - The old block is not used after this, as is deferred to the caller.
+    unsafe {
+        core::ptr::copy_nonoverlapping(
+            ptr.as_ptr(),
+            new_ptr.as_ptr(),
+            old_layout.size().min(new_layout.size()),
+        );
+        allocator.deallocate(ptr, old_layout);
+    }
+
+    Some(new_ptr)
+}
+
+unsafe impl<A: Allocator + ?Sized> Allocator for &A {
+    #[inline]
+    unsafe fn allocate(&self, layout: Layout) -> Option<NonNull<u8>> {
+        unsafe { (**self).allocate(layout) }
+    }
+
+    #[inline]
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        unsafe { (**self).deallocate(ptr, layout) }
+    }
+
+    #[inline]
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Option<NonNull<u8>> {
+        unsafe { (**self).grow(ptr, old_layout, new_layout) }
+    }
+
+    #[inline]
+    unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Option<NonNull<u8>> {
+        unsafe { (**self).shrink(ptr, old_layout, new_layout) }
+    }
+}
+
+/// The global allocator, as used by `Box` and `Vec`
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Global;
+
+unsafe impl Allocator for Global {
+    #[inline]
+    unsafe fn allocate(&self, layout: Layout) -> Option<NonNull<u8>> {
+        // SAFETY: The layout has a non-zero size, as is deferred to the caller
+        NonNull::new(unsafe { alloc::alloc(layout) })
+    }
+
+    #[inline]
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        // SAFETY: The block was allocated with the same layout, as is deferred to the caller
+        unsafe { alloc::dealloc(ptr.as_ptr(), layout) }
+    }
+
+    #[inline]
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Option<NonNull<u8>> {
+        debug_assert_eq!(old_layout.align(), new_layout.align());
+
+        // SAFETY: The block was allocated with the old layout, and the new size is non-zero and
+        // valid for the alignment, as is deferred to the caller
+        NonNull::new(unsafe { alloc::realloc(ptr.as_ptr(), old_layout, new_layout.size()) })
+    }
+
+    #[inline]
+    unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Option<NonNull<u8>> {
+        debug_assert_eq!(old_layout.align(), new_layout.align());
+
+        // SAFETY: The block was allocated with the old layout, and the new size is non-zero, as is
+        // deferred to the caller
+        NonNull::new(unsafe { alloc::realloc(ptr.as_ptr(), old_layout, new_layout.size()) })
+    }
+}
+
+/// Test allocator that counts the blocks it hands out from the global allocator, and relies on the
+/// provided `grow` and `shrink`, so that they are exercised too
+#[cfg(test)]
+#[derive(Default)]
+pub(crate) struct Counting {
+    /// Every allocation, including those made to grow or shrink a block
+    pub(crate) allocations: core::cell::Cell<usize>,
+    /// Blocks that have not been deallocated yet
+    pub(crate) live: core::cell::Cell<usize>,
+}
+
+#[cfg(test)]
+unsafe impl Allocator for Counting {
+    unsafe fn allocate(&self, layout: Layout) -> Option<NonNull<u8>> {
+        self.allocations.set(self.allocations.get() + 1);
+        self.live.set(self.live.get() + 1);
+        unsafe { Global.allocate(layout) }
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        self.live.set(self.live.get() - 1);
+        unsafe { Global.deallocate(ptr, layout) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reallocate_fallback() {
+        let allocator = Counting::default();
+        let old_layout = Layout::array::<u32>(4).unwrap();
+        let new_layout = Layout::array::<u32>(16).unwrap();
+
+        unsafe {
+            let ptr = allocator.allocate(old_layout).unwrap().cast::<u32>();
+            for i in 0..4 {
+                ptr.add(i).write(i as u32);
+            }
+
+            let ptr = allocator
+                .grow(ptr.cast(), old_layout, new_layout)
+                .unwrap()
+                .cast::<u32>();
+            assert_eq!(allocator.live.get(), 1);
+            for i in 0..4 {
+                assert_eq!(ptr.add(i).read(), i as u32);
+            }
+
+            let small_layout = Layout::array::<u32>(2).unwrap();
+            let ptr = allocator
+                .shrink(ptr.cast(), new_layout, small_layout)
+                .unwrap()
+                .cast::<u32>();
+            assert_eq!(allocator.live.get(), 1);
+            assert_eq!(ptr.read(), 0);
+            assert_eq!(ptr.add(1).read(), 1);
+
+            allocator.deallocate(ptr.cast(), small_layout);
+        }
+        assert_eq!(allocator.live.get(), 0);
+    }
+}
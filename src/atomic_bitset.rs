@@ -0,0 +1,375 @@
+use core::{fmt, slice::Iter};
+
+#[cfg(loom)]
+use loom::sync::atomic::{AtomicU64, Ordering};
+#[cfg(not(loom))]
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::bitset::{
+    words_for_bits, Element, BITS_PER_ELEMENT, DEFAULT_CAPACITY, DEFAULT_ELEMENTS,
+};
+use crate::{BitIndexOutOfBounds, BitSet};
+
+/// A bitset that can be modified through a shared reference, so that several threads can set and
+/// clear bits concurrently. Each operation takes the memory [Ordering] to use, in the same way as
+/// the std atomics.
+///
+/// Operations on a single bit are atomic, but operations on the whole set, like
+/// [AtomicBitSet::swap_to_snapshot], are only atomic for each element in turn. A bit set
+/// concurrently with a snapshot will be either in the snapshot or left in the set, but never lost.
+pub struct AtomicBitSet<const N: usize = DEFAULT_CAPACITY, const L: usize = DEFAULT_ELEMENTS> {
+    bits: [AtomicU64; L],
+}
+
+impl<const N: usize, const L: usize> AtomicBitSet<N, L> {
+    pub fn new() -> Self {
+        const {
+            assert!(
+                L == words_for_bits(N),
+                "`L` must be the number of elements needed to store `N` bits"
+            )
+        };
+
+        Self {
+            bits: core::array::from_fn(|_| AtomicU64::new(0)),
+        }
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Sets the bit at `index`
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds
+    pub fn set(&self, index: usize, order: Ordering) {
+        self.fetch_set(index, order);
+    }
+
+    /// Clears the bit at `index`
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds
+    pub fn clear(&self, index: usize, order: Ordering) {
+        self.fetch_clear(index, order);
+    }
+
+    /// Returns true if the bit at `index` is set
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds
+    pub fn test(&self, index: usize, order: Ordering) -> bool {
+        let (element, mask) = self.locate(index);
+        self.bits[element].load(order) & mask != 0
+    }
+
+    /// Sets the bit at `index`, and returns true if it was already set. Exactly one of several
+    /// threads setting the same bit will see false.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds
+    pub fn fetch_set(&self, index: usize, order: Ordering) -> bool {
+        let (element, mask) = self.locate(index);
+        self.bits[element].fetch_or(mask, order) & mask != 0
+    }
+
+    /// Clears the bit at `index`, and returns true if it was set
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds
+    pub fn fetch_clear(&self, index: usize, order: Ordering) -> bool {
+        let (element, mask) = self.locate(index);
+        self.bits[element].fetch_and(!mask, order) & mask != 0
+    }
+
+    /// Returns a copy of the bits, without clearing them
+    pub fn load(&self, order: Ordering) -> BitSet<N, L> {
+        let mut snapshot = BitSet::new();
+        for (dst, src) in snapshot.bits.iter_mut().zip(&self.bits) {
+            *dst = src.load(order);
+        }
+        snapshot
+    }
+
+    /// Takes all of the bits, leaving the set empty
+    pub fn swap_to_snapshot(&self, order: Ordering) -> BitSet<N, L> {
+        let mut snapshot = BitSet::new();
+        for (dst, src) in snapshot.bits.iter_mut().zip(&self.bits) {
+            *dst = src.swap(0, order);
+        }
+        snapshot
+    }
+
+    /// Iterates over the indices of the set bits. Each element is loaded as the iterator reaches
+    /// it, so bits changed concurrently may or may not be seen.
+    pub fn iter_indices(&self, order: Ordering) -> AtomicSetBitsIter<'_> {
+        AtomicSetBitsIter {
+            elements: self.bits.iter(),
+            order,
+            current: 0,
+            base: 0,
+        }
+    }
+
+    pub fn into_inner(self) -> BitSet<N, L> {
+        let mut bit_set = BitSet::new();
+        for (dst, src) in bit_set.bits.iter_mut().zip(self.bits) {
+            *dst = src.into_inner();
+        }
+        bit_set
+    }
+
+    fn locate(&self, index: usize) -> (usize, Element) {
+        if index >= N {
+            panic!("{}", BitIndexOutOfBounds { index, capacity: N });
+        }
+        (index / BITS_PER_ELEMENT, 1 << (index % BITS_PER_ELEMENT))
+    }
+}
+
+impl<const N: usize, const L: usize> Default for AtomicBitSet<N, L> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize, const L: usize> From<BitSet<N, L>> for AtomicBitSet<N, L> {
+    fn from(bit_set: BitSet<N, L>) -> Self {
+        Self {
+            bits: bit_set.bits.map(AtomicU64::new),
+        }
+    }
+}
+
+impl<const N: usize, const L: usize> fmt::Debug for AtomicBitSet<N, L> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.load(Ordering::Relaxed), f)
+    }
+}
+
+pub struct AtomicSetBitsIter<'a> {
+    elements: Iter<'a, AtomicU64>,
+    order: Ordering,
+    /// Remaining set bits of the element that was last loaded
+    current: Element,
+    /// Index of the first bit of `current`
+    base: usize,
+}
+
+impl<'a> Iterator for AtomicSetBitsIter<'a> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.current == 0 {
+            self.current = self.elements.next()?.load(self.order);
+            self.base += BITS_PER_ELEMENT;
+        }
+
+        let index = self.current.trailing_zeros() as usize;
+        // clear the lowest set bit
+        self.current &= self.current - 1;
+        Some(self.base - BITS_PER_ELEMENT + index)
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use std::{sync::Barrier, thread};
+
+    use super::*;
+
+    #[test]
+    fn set_clear_test() {
+        let bits = AtomicBitSet::<130, 3>::new();
+        bits.set(3, Ordering::Relaxed);
+        bits.set(129, Ordering::Relaxed);
+        assert!(bits.test(3, Ordering::Relaxed));
+        assert!(bits.test(129, Ordering::Relaxed));
+        assert!(!bits.test(64, Ordering::Relaxed));
+
+        assert!(!bits.fetch_set(64, Ordering::Relaxed));
+        assert!(bits.fetch_set(64, Ordering::Relaxed));
+        assert!(bits.fetch_clear(3, Ordering::Relaxed));
+        assert!(!bits.fetch_clear(3, Ordering::Relaxed));
+        bits.clear(129, Ordering::Relaxed);
+
+        assert_eq!(
+            bits.iter_indices(Ordering::Relaxed).collect::<Vec<_>>(),
+            [64]
+        );
+    }
+
+    #[test]
+    fn defaults_match_bitset() {
+        // only compiles if both types default to the same parameters
+        let bits: AtomicBitSet = AtomicBitSet::from(BitSet::default());
+        let snapshot: BitSet = bits.load(Ordering::Relaxed);
+        assert_eq!(snapshot.capacity(), bits.capacity());
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "bit index is out of bounds: the index is 130 but the capacity is 130"
+    )]
+    fn out_of_bounds() {
+        AtomicBitSet::<130, 3>::new().set(130, Ordering::Relaxed);
+    }
+
+    #[test]
+    fn snapshots() {
+        let bit_set: BitSet<130, 3> = [0, 63, 64, 129].into_iter().collect();
+        let bits = AtomicBitSet::from(bit_set.clone());
+
+        assert_eq!(
+            bits.iter_indices(Ordering::Relaxed).collect::<Vec<_>>(),
+            [0, 63, 64, 129]
+        );
+        assert_eq!(bits.load(Ordering::Relaxed), bit_set);
+        assert_eq!(bits.swap_to_snapshot(Ordering::AcqRel), bit_set);
+        assert_eq!(bits.load(Ordering::Relaxed), BitSet::new());
+
+        bits.set(100, Ordering::Relaxed);
+        assert_eq!(bits.into_inner(), BitSet::from_index(100));
+    }
+
+    #[test]
+    fn threads_fetch_set() {
+        const THREADS: usize = 4;
+        let bits = AtomicBitSet::<1000, 16>::new();
+        let first = AtomicU64::new(0);
+
+        // every thread races to set every bit, but each bit is only newly set once
+        thread::scope(|s| {
+            for _ in 0..THREADS {
+                s.spawn(|| {
+                    for i in 0..1000 {
+                        if !bits.fetch_set(i, Ordering::Relaxed) {
+                            first.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                });
+            }
+        });
+
+        assert_eq!(first.into_inner(), 1000);
+        assert_eq!(bits.into_inner().count_ones(), 1000);
+    }
+
+    #[test]
+    fn threads_swap_to_snapshot() {
+        const THREADS: usize = 4;
+        const PER_THREAD: usize = 250;
+        let bits = AtomicBitSet::<1000, 16>::new();
+        let barrier = Barrier::new(THREADS + 1);
+
+        // each worker marks its own chunks, while the main thread repeatedly takes snapshots
+        let taken = thread::scope(|s| {
+            for t in 0..THREADS {
+                let (bits, barrier) = (&bits, &barrier);
+                s.spawn(move || {
+                    barrier.wait();
+                    for i in 0..PER_THREAD {
+                        bits.set(t + i * THREADS, Ordering::Release);
+                    }
+                });
+            }
+
+            barrier.wait();
+            let mut taken = Vec::new();
+            for _ in 0..100 {
+                taken.push(bits.swap_to_snapshot(Ordering::Acquire));
+            }
+            taken
+        });
+
+        // no bit can be taken twice, or lost
+        let mut all = bits.into_inner();
+        for snapshot in taken {
+            assert!(all.is_disjoint(&snapshot));
+            all |= snapshot;
+        }
+        assert_eq!(all.count_ones(), THREADS * PER_THREAD);
+    }
+}
+
+/// Run with `RUSTFLAGS="--cfg loom" cargo test --release atomic_bitset`
+#[cfg(all(test, loom))]
+mod loom_tests {
+    use loom::{sync::Arc, thread};
+
+    use super::*;
+
+    #[test]
+    fn fetch_set_once() {
+        loom::model(|| {
+            let bits = Arc::new(AtomicBitSet::<128, 2>::new());
+
+            let handles: Vec<_> = (0..2)
+                .map(|_| {
+                    let bits = bits.clone();
+                    thread::spawn(move || bits.fetch_set(70, Ordering::Relaxed))
+                })
+                .collect();
+
+            let newly_set = handles
+                .into_iter()
+                .map(|handle| !handle.join().unwrap())
+                .filter(|newly_set| *newly_set)
+                .count();
+            assert_eq!(newly_set, 1);
+        });
+    }
+
+    #[test]
+    fn swap_to_snapshot_loses_nothing() {
+        loom::model(|| {
+            let bits = Arc::new(AtomicBitSet::<128, 2>::new());
+
+            let worker = {
+                let bits = bits.clone();
+                thread::spawn(move || {
+                    bits.set(1, Ordering::Release);
+                    bits.set(64, Ordering::Release);
+                })
+            };
+
+            let snapshot = bits.swap_to_snapshot(Ordering::Acquire);
+            worker.join().unwrap();
+            let rest = bits.swap_to_snapshot(Ordering::Acquire);
+
+            assert!(snapshot.is_disjoint(&rest));
+            assert_eq!(
+                (snapshot | rest).iter_indices().collect::<Vec<_>>(),
+                [1, 64]
+            );
+        });
+    }
+
+    #[test]
+    fn release_acquire_publishes() {
+        use loom::cell::UnsafeCell;
+
+        loom::model(|| {
+            let bits = Arc::new(AtomicBitSet::<64, 1>::new());
+            let data = Arc::new(UnsafeCell::new(0));
+
+            let worker = {
+                let (bits, data) = (bits.clone(), data.clone());
+                thread::spawn(move || {
+                    // SAFETY: the reader only touches `data` once it sees the flag
+                    data.with_mut(|data| unsafe { *data = 42 });
+                    bits.set(5, Ordering::Release);
+                })
+            };
+
+            if bits.test(5, Ordering::Acquire) {
+                // SAFETY: the release store of the flag happens after the write
+                let value = data.with(|data| unsafe { *data });
+                assert_eq!(value, 42);
+            }
+
+            worker.join().unwrap();
+        });
+    }
+}
@@ -0,0 +1,563 @@
+use core::{
+    fmt,
+    hash::{Hash, Hasher},
+    ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not, RangeBounds},
+};
+
+use crate::bitset::{self, words_for_bits, Element, BITS_PER_ELEMENT};
+use crate::{BitIndexOutOfBounds, BitSet, SetBitsIter};
+
+/// A heap allocated bitset, whose length can change at runtime. This mirrors the API of [BitSet].
+///
+/// Operations between bitsets of different lengths treat any missing bits as clear, and produce a
+/// bitset as long as the longer of the two. For the same reason, bitsets with the same bits set are
+/// equal, whatever their lengths.
+#[derive(Clone, Default)]
+pub struct BitVec {
+    bits: Vec<Element>,
+    /// The number of bits in the set. Bits beyond this in the last element are always kept clear.
+    len: usize,
+}
+
+impl BitVec {
+    pub fn new() -> Self {
+        Self::with_len(0)
+    }
+
+    /// Creates a bitset of `len` bits, all of which are clear
+    pub fn with_len(len: usize) -> Self {
+        Self {
+            bits: vec![0; words_for_bits(len)],
+            len,
+        }
+    }
+
+    /// The number of bits that can be stored in this bitset, without growing it
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Grows the bitset so that it is at least `len` bits long. New bits are clear.
+    pub fn grow(&mut self, len: usize) {
+        if len > self.len {
+            self.resize(len);
+        }
+    }
+
+    /// Shortens the bitset to `len` bits, discarding the rest. Does nothing if `len` is greater
+    /// than the current length.
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.len {
+            self.resize(len);
+        }
+    }
+
+    /// Changes the length of the bitset to `len` bits. New bits are clear.
+    pub fn resize(&mut self, len: usize) {
+        self.bits.resize(words_for_bits(len), 0);
+        self.len = len;
+        self.mask_tail();
+    }
+
+    /// Clears any bits in the last element that are beyond the logical length of the set
+    #[inline]
+    fn mask_tail(&mut self) {
+        if let Some(last) = self.bits.last_mut() {
+            *last &= bitset::tail_mask(self.len);
+        }
+    }
+
+    /// # Panics
+    /// Panics if `index` is not less than the length of the bitset
+    #[inline]
+    fn locate(&self, index: usize) -> (usize, usize) {
+        match self.try_locate(index) {
+            Ok(location) => location,
+            Err(error) => panic!("{error}"),
+        }
+    }
+
+    #[inline]
+    fn try_locate(&self, index: usize) -> Result<(usize, usize), BitIndexOutOfBounds> {
+        if index < self.len {
+            Ok((index / BITS_PER_ELEMENT, index % BITS_PER_ELEMENT))
+        } else {
+            Err(BitIndexOutOfBounds {
+                index,
+                capacity: self.len,
+            })
+        }
+    }
+
+    /// # Panics
+    /// Panics if `index` is not less than the length of the bitset
+    pub fn set(&mut self, index: usize) {
+        let (i, j) = self.locate(index);
+        self.bits[i] |= 1 << j;
+    }
+
+    /// # Panics
+    /// Panics if `index` is not less than the length of the bitset
+    pub fn clear(&mut self, index: usize) {
+        let (i, j) = self.locate(index);
+        self.bits[i] &= !(1 << j);
+    }
+
+    /// # Panics
+    /// Panics if `index` is not less than the length of the bitset
+    pub fn test(&self, index: usize) -> bool {
+        let (i, j) = self.locate(index);
+        self.bits[i] & (1 << j) != 0
+    }
+
+    /// Flips the bit at `index`
+    ///
+    /// # Panics
+    /// Panics if `index` is not less than the length of the bitset
+    pub fn toggle(&mut self, index: usize) {
+        let (i, j) = self.locate(index);
+        self.bits[i] ^= 1 << j;
+    }
+
+    /// Sets the bit at `index`, or returns an error if it is out of bounds
+    pub fn try_set(&mut self, index: usize) -> Result<(), BitIndexOutOfBounds> {
+        let (i, j) = self.try_locate(index)?;
+        self.bits[i] |= 1 << j;
+        Ok(())
+    }
+
+    /// Clears the bit at `index`, or returns an error if it is out of bounds
+    pub fn try_clear(&mut self, index: usize) -> Result<(), BitIndexOutOfBounds> {
+        let (i, j) = self.try_locate(index)?;
+        self.bits[i] &= !(1 << j);
+        Ok(())
+    }
+
+    /// Returns whether the bit at `index` is set, or `None` if it is out of bounds
+    pub fn try_test(&self, index: usize) -> Option<bool> {
+        let (i, j) = self.try_locate(index).ok()?;
+        Some(self.bits[i] & (1 << j) != 0)
+    }
+
+    /// Sets every bit within `range`
+    ///
+    /// # Panics
+    /// Panics if the range is decreasing, or extends beyond the length of the bitset
+    pub fn set_range(&mut self, range: impl RangeBounds<usize>) {
+        for (i, mask) in bitset::range_masks(self.len, range) {
+            self.bits[i] |= mask;
+        }
+    }
+
+    /// Clears every bit within `range`
+    ///
+    /// # Panics
+    /// Panics if the range is decreasing, or extends beyond the length of the bitset
+    pub fn clear_range(&mut self, range: impl RangeBounds<usize>) {
+        for (i, mask) in bitset::range_masks(self.len, range) {
+            self.bits[i] &= !mask;
+        }
+    }
+
+    /// Flips every bit within `range`
+    ///
+    /// # Panics
+    /// Panics if the range is decreasing, or extends beyond the length of the bitset
+    pub fn flip_range(&mut self, range: impl RangeBounds<usize>) {
+        for (i, mask) in bitset::range_masks(self.len, range) {
+            self.bits[i] ^= mask;
+        }
+    }
+
+    /// Counts the set bits within `range`
+    ///
+    /// # Panics
+    /// Panics if the range is decreasing, or extends beyond the length of the bitset
+    pub fn count_ones_in(&self, range: impl RangeBounds<usize>) -> usize {
+        bitset::range_masks(self.len, range)
+            .map(|(i, mask)| (self.bits[i] & mask).count_ones() as usize)
+            .sum()
+    }
+
+    /// Returns true if any bit within `range` is set
+    ///
+    /// # Panics
+    /// Panics if the range is decreasing, or extends beyond the length of the bitset
+    pub fn any_in(&self, range: impl RangeBounds<usize>) -> bool {
+        bitset::range_masks(self.len, range).any(|(i, mask)| self.bits[i] & mask != 0)
+    }
+
+    /// Returns true if other is a subset of self
+    pub fn contains(&self, other: &Self) -> bool {
+        // any bits in `other` beyond the length of `self` must be clear
+        other
+            .bits
+            .iter()
+            .enumerate()
+            .all(|(i, bits)| self.bits.get(i).copied().unwrap_or(0) & bits == *bits)
+    }
+
+    pub fn contains_none(&self, other: &Self) -> bool {
+        self.bits.iter().zip(&other.bits).all(|(a, b)| a & b == 0)
+    }
+
+    pub fn contains_some(&self, other: &Self) -> bool {
+        !self.contains_none(other)
+    }
+
+    /// Returns bits that are in self, without bits in other
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut result = self.clone();
+        result.grow(other.len);
+        for (a, b) in result.bits.iter_mut().zip(&other.bits) {
+            *a &= !b;
+        }
+        result
+    }
+
+    /// Returns bits that are in self or in other, but not in both
+    pub fn symmetric_difference(&self, other: &Self) -> Self {
+        self ^ other
+    }
+
+    /// Returns bits that are in self and other
+    pub fn intersection(&self, other: &Self) -> Self {
+        self & other
+    }
+
+    /// Returns bits that are in self and/or other
+    pub fn union(&self, other: &Self) -> Self {
+        self | other
+    }
+
+    pub fn leading_zeros(&self) -> usize {
+        bitset::leading_zeros(&self.bits, self.len)
+    }
+
+    pub fn trailing_zeros(&self) -> usize {
+        bitset::trailing_zeros(&self.bits, self.len)
+    }
+
+    /// Returns the index of the lowest set bit
+    #[inline]
+    pub fn first_one(&self) -> Option<usize> {
+        self.next_one(0)
+    }
+
+    /// Returns the index of the highest set bit
+    #[inline]
+    pub fn last_one(&self) -> Option<usize> {
+        self.prev_one(usize::MAX)
+    }
+
+    /// Returns the index of the lowest clear bit
+    #[inline]
+    pub fn first_zero(&self) -> Option<usize> {
+        self.next_zero(0)
+    }
+
+    /// Returns the index of the lowest set bit that is at or after `from`
+    #[inline]
+    pub fn next_one(&self, from: usize) -> Option<usize> {
+        bitset::next_one(&self.bits, self.len, from)
+    }
+
+    /// Returns the index of the lowest clear bit that is at or after `from`
+    #[inline]
+    pub fn next_zero(&self, from: usize) -> Option<usize> {
+        bitset::next_zero(&self.bits, self.len, from)
+    }
+
+    /// Returns the index of the highest set bit that is at or before `from`
+    #[inline]
+    pub fn prev_one(&self, from: usize) -> Option<usize> {
+        bitset::prev_one(&self.bits, self.len, from)
+    }
+
+    /// Returns the number of set bits before `index`
+    ///
+    /// # Panics
+    /// Panics if `index` is greater than the length of the bitset
+    #[inline]
+    pub fn rank(&self, index: usize) -> usize {
+        self.count_ones_in(..index)
+    }
+
+    /// Returns the index of the `k`th set bit, counting from zero, so that `rank(select(k)) == k`
+    #[inline]
+    pub fn select(&self, k: usize) -> Option<usize> {
+        bitset::select(&self.bits, k)
+    }
+
+    pub fn count_ones(&self) -> usize {
+        bitset::count_ones(&self.bits)
+    }
+
+    /// Returns true if no bits are set, regardless of the length of the bitset
+    pub fn is_empty(&self) -> bool {
+        bitset::is_zero(&self.bits)
+    }
+
+    pub fn iter_indices(&self) -> SetBitsIter<'_> {
+        SetBitsIter::new(&self.bits)
+    }
+}
+
+impl BitVec {
+    /// The elements without any trailing empty ones, which are all that bitsets of different
+    /// lengths need to compare
+    fn trimmed_bits(&self) -> &[Element] {
+        let len = self
+            .bits
+            .iter()
+            .rposition(|bits| *bits != 0)
+            .map_or(0, |i| i + 1);
+        &self.bits[..len]
+    }
+}
+
+impl PartialEq for BitVec {
+    fn eq(&self, other: &Self) -> bool {
+        self.trimmed_bits() == other.trimmed_bits()
+    }
+}
+
+impl Eq for BitVec {}
+
+impl Hash for BitVec {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.trimmed_bits().hash(state);
+    }
+}
+
+impl fmt::Debug for BitVec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter_indices()).finish()
+    }
+}
+
+impl<const N: usize, const L: usize> From<BitSet<N, L>> for BitVec {
+    fn from(bit_set: BitSet<N, L>) -> Self {
+        Self {
+            bits: bit_set.bits.to_vec(),
+            len: N,
+        }
+    }
+}
+
+/// Any bits at or beyond `N` are discarded, and any bits missing from the [BitVec] are clear.
+impl<const N: usize, const L: usize> From<BitVec> for BitSet<N, L> {
+    fn from(bit_vec: BitVec) -> Self {
+        let mut bit_set = BitSet::new();
+        let count = L.min(bit_vec.bits.len());
+        bit_set.bits[..count].copy_from_slice(&bit_vec.bits[..count]);
+        bit_set.mask_tail();
+        bit_set
+    }
+}
+
+impl Not for &BitVec {
+    type Output = BitVec;
+
+    fn not(self) -> Self::Output {
+        let mut result = BitVec {
+            bits: self.bits.iter().map(|bits| !bits).collect(),
+            len: self.len,
+        };
+        result.mask_tail();
+        result
+    }
+}
+
+macro_rules! impl_bitwise_assign {
+    ( $trait:ident, $fn:ident, $op:tt ) => {
+
+        impl $trait<&BitVec> for BitVec {
+            fn $fn(&mut self, rhs: &BitVec) {
+                // missing bits on either side are treated as clear
+                self.grow(rhs.len);
+                for (i, bits) in self.bits.iter_mut().enumerate() {
+                    *bits $op rhs.bits.get(i).copied().unwrap_or(0);
+                }
+            }
+        }
+
+        impl $trait<&mut BitVec> for BitVec {
+            fn $fn(&mut self, rhs: &mut BitVec) {
+                self.$fn(&*rhs);
+            }
+        }
+
+    };
+}
+
+macro_rules! impl_bitwise {
+    ( $trait:ident, $fn:ident, $op:tt ) => {
+
+        impl $trait for &BitVec {
+            type Output = BitVec;
+
+            fn $fn(self, rhs: Self) -> Self::Output {
+                let mut result = self.clone();
+                result $op rhs;
+                result
+            }
+        }
+
+    };
+}
+
+impl_bitwise!(BitAnd, bitand, &=);
+impl_bitwise!(BitOr, bitor, |=);
+impl_bitwise!(BitXor, bitxor, ^=);
+impl_bitwise_assign!(BitAndAssign, bitand_assign, &=);
+impl_bitwise_assign!(BitOrAssign, bitor_assign, |=);
+impl_bitwise_assign!(BitXorAssign, bitxor_assign, ^=);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bit_vec() {
+        let mut bit_vec = BitVec::with_len(100);
+        assert_eq!(bit_vec.len(), 100);
+        assert!(bit_vec.is_empty());
+
+        bit_vec.set(17);
+        bit_vec.set(99);
+        assert!(bit_vec.test(17));
+        assert!(bit_vec.test(99));
+        assert_eq!(bit_vec.count_ones(), 2);
+        assert_eq!(bit_vec.leading_zeros(), 0);
+        assert_eq!(bit_vec.trailing_zeros(), 17);
+
+        bit_vec.clear(17);
+        assert!(!bit_vec.test(17));
+        assert_eq!(bit_vec.iter_indices().collect::<Vec<_>>(), vec![99]);
+
+        assert_eq!(
+            bit_vec.try_set(100),
+            Err(BitIndexOutOfBounds {
+                index: 100,
+                capacity: 100
+            })
+        );
+        assert_eq!(bit_vec.try_test(100), None);
+    }
+
+    #[test]
+    fn test_bit_vec_resize() {
+        let mut bit_vec = BitVec::new();
+        assert_eq!(bit_vec.len(), 0);
+        assert_eq!(bit_vec.iter_indices().next(), None);
+
+        bit_vec.grow(70);
+        assert_eq!(bit_vec.len(), 70);
+        bit_vec.set_range(..);
+        assert_eq!(bit_vec.count_ones(), 70);
+
+        // growing to a smaller length does nothing
+        bit_vec.grow(10);
+        assert_eq!(bit_vec.len(), 70);
+
+        // truncated bits are gone for good, and don't come back when growing again
+        bit_vec.truncate(65);
+        assert_eq!(bit_vec.count_ones(), 65);
+        bit_vec.resize(200);
+        assert_eq!(bit_vec.count_ones(), 65);
+        assert_eq!(bit_vec.last_one(), Some(64));
+
+        bit_vec.truncate(3);
+        assert_eq!(bit_vec.count_ones(), 3);
+        assert_eq!((!&bit_vec).count_ones(), 0);
+    }
+
+    #[test]
+    fn test_bit_vec_different_lengths() {
+        let mut short = BitVec::with_len(10);
+        short.set(3);
+        short.set(7);
+
+        let mut long = BitVec::with_len(130);
+        long.set(3);
+        long.set(129);
+
+        let and = &short & &long;
+        assert_eq!(and.len(), 130);
+        assert_eq!(and.iter_indices().collect::<Vec<_>>(), vec![3]);
+
+        let or = &short | &long;
+        assert_eq!(or.len(), 130);
+        assert_eq!(or.iter_indices().collect::<Vec<_>>(), vec![3, 7, 129]);
+
+        let xor = &long ^ &short;
+        assert_eq!(xor.len(), 130);
+        assert_eq!(xor.iter_indices().collect::<Vec<_>>(), vec![7, 129]);
+
+        let difference = short.difference(&long);
+        assert_eq!(difference.iter_indices().collect::<Vec<_>>(), vec![7]);
+
+        assert!(or.contains(&short));
+        assert!(or.contains(&long));
+        assert!(!short.contains(&long));
+        assert!(short.contains(&and));
+        assert!(short.contains_some(&long));
+        assert!(difference.contains_none(&long));
+
+        let mut assigned = short.clone();
+        assigned |= &long;
+        assert_eq!(assigned, or);
+    }
+
+    #[test]
+    fn test_bit_vec_eq_different_lengths() {
+        use std::hash::{BuildHasher, RandomState};
+
+        let mut small = BitVec::with_len(10);
+        small.set(3);
+        let mut same = BitVec::with_len(100);
+        same.set(3);
+        let mut big = BitVec::with_len(100);
+        big.set(3);
+        big.set(50);
+
+        // missing words are treated as zero, so the lengths don't matter
+        assert_eq!(small, same);
+        let hasher = RandomState::new();
+        assert_eq!(hasher.hash_one(&small), hasher.hash_one(&same));
+
+        assert!(big.contains(&small));
+        assert_eq!(&small & &big, small);
+        assert_ne!(small, big);
+        assert_eq!(BitVec::with_len(200), BitVec::new());
+
+        assert_eq!(format!("{small:?}"), "{3}");
+        assert_eq!(format!("{big:?}"), "{3, 50}");
+        assert_eq!(format!("{:?}", BitVec::new()), "{}");
+    }
+
+    #[test]
+    fn test_bit_vec_bitset_conversion() {
+        let mut bit_set = BitSet::<100, 2>::new();
+        bit_set.set(5);
+        bit_set.set(99);
+
+        let bit_vec = BitVec::from(bit_set.clone());
+        assert_eq!(bit_vec.len(), 100);
+        assert_eq!(bit_vec.iter_indices().collect::<Vec<_>>(), vec![5, 99]);
+        assert_eq!(BitSet::<100, 2>::from(bit_vec.clone()), bit_set);
+
+        // bits beyond the width of the bitset are discarded
+        let narrow = BitSet::<64, 1>::from(bit_vec.clone());
+        assert_eq!(narrow.iter_indices().collect::<Vec<_>>(), vec![5]);
+
+        let narrow = BitSet::<6, 1>::from(bit_vec);
+        assert_eq!(narrow.iter_indices().collect::<Vec<_>>(), vec![5]);
+
+        // missing bits are clear
+        let wide = BitSet::<256, 4>::from(BitVec::from(narrow));
+        assert_eq!(wide.iter_indices().collect::<Vec<_>>(), vec![5]);
+    }
+}
@@ -1,61 +1,592 @@
 use core::{
-    ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not},
+    cmp::Ordering,
+    fmt,
+    num::ParseIntError,
+    ops::{
+        BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Bound, Not, RangeBounds,
+        Shl, ShlAssign, Shr, ShrAssign,
+    },
     slice::Iter,
+    str::FromStr,
 };
 
 // u64 allows for automatic SIMD vectorization on x86_64, but u128 is faster for
 // leading/trailing zeros (?). We could coerce compiler to use u64 for SIMD via transmute,
 // but not sure how to do that with const generics.
-const DEFAULT_CAPACITY: usize = 1;
-const BITS_PER_ELEMENT: usize = 64;
-type Element = u64;
+pub(crate) const DEFAULT_CAPACITY: usize = BITS_PER_ELEMENT;
+pub(crate) const DEFAULT_ELEMENTS: usize = words_for_bits(DEFAULT_CAPACITY);
+pub(crate) const BITS_PER_ELEMENT: usize = 64;
+pub(crate) type Element = u64;
+
+/// Returns the number of `Element`s needed to store `bits` bits, for use as the `L` parameter of
+/// [BitSet], e.g. `BitSet<100, { words_for_bits(100) }>`.
+pub const fn words_for_bits(bits: usize) -> usize {
+    bits.div_ceil(BITS_PER_ELEMENT)
+}
+
+/// The type of a [BitSet] with the given number of bits, filling in the number of elements, e.g.
+/// `bitset!(100)` is `BitSet<100, { words_for_bits(100) }>`.
+#[macro_export]
+macro_rules! bitset {
+    ($bits:expr) => {
+        $crate::BitSet<{ $bits }, { $crate::words_for_bits($bits) }>
+    };
+}
+
+// The functions below operate on the elements of any bitset, given its logical length in bits, so
+// they can be shared between the fixed and dynamically sized bitsets. They all assume that the
+// unused bits at the end of the last element are clear.
+
+/// Mask of the bits in the last element that are within the logical length of a bitset
+pub(crate) const fn tail_mask(len: usize) -> Element {
+    match len % BITS_PER_ELEMENT {
+        0 => Element::MAX,
+        rem => (1 << rem) - 1,
+    }
+}
 
-/// A bitset with a fixed length, configurable via const generics where `L` is the number of `Element`s
-/// used to store the bits.
+/// Returns the index of each element that overlaps `range`, along with a mask of the bits
+/// within that element that fall inside the range.
 ///
-/// Defaults to 1 `Element`.
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
-pub struct BitSet<const L: usize = DEFAULT_CAPACITY> {
-    bits: [Element; L],
+/// # Panics
+/// Panics if the range is decreasing, or extends beyond `len`
+pub(crate) fn range_masks(
+    len: usize,
+    range: impl RangeBounds<usize>,
+) -> impl Iterator<Item = (usize, Element)> {
+    let start = match range.start_bound() {
+        Bound::Included(&start) => start,
+        Bound::Excluded(&start) => start + 1,
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&end) => end + 1,
+        Bound::Excluded(&end) => end,
+        Bound::Unbounded => len,
+    };
+
+    assert!(
+        start <= end,
+        "range start {start} is greater than range end {end}"
+    );
+    assert!(
+        end <= len,
+        "range end {end} is out of bounds for a bitset with capacity {len}"
+    );
+
+    // an empty range has no elements at all, rather than an element with an empty mask
+    let elements = if start == end {
+        0..0
+    } else {
+        start / BITS_PER_ELEMENT..(end - 1) / BITS_PER_ELEMENT + 1
+    };
+
+    elements.map(move |i| {
+        let element_start = i * BITS_PER_ELEMENT;
+        let lo = start.max(element_start) - element_start;
+        let hi = end.min(element_start + BITS_PER_ELEMENT) - element_start;
+
+        (i, (Element::MAX >> (BITS_PER_ELEMENT - (hi - lo))) << lo)
+    })
+}
+
+/// The number of elements at which the SIMD kernels start to pay for their runtime feature
+/// detection
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+const SIMD_THRESHOLD: usize = 4;
+
+macro_rules! impl_binary_kernel {
+    ( $fn:ident, $op:tt ) => {
+
+        pub(crate) fn $fn(dst: &mut [Element], src: &[Element]) {
+            #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+            if dst.len() >= SIMD_THRESHOLD {
+                return crate::bitset_simd::$fn(dst, src);
+            }
+
+            for (dst, src) in dst.iter_mut().zip(src) {
+                *dst $op src;
+            }
+        }
+
+    };
+}
+
+impl_binary_kernel!(and_assign, &=);
+impl_binary_kernel!(or_assign, |=);
+impl_binary_kernel!(xor_assign, ^=);
+
+pub(crate) fn not_assign(dst: &mut [Element]) {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    if dst.len() >= SIMD_THRESHOLD {
+        return crate::bitset_simd::not_assign(dst);
+    }
+
+    for bits in dst.iter_mut() {
+        *bits = !*bits;
+    }
 }
 
-impl<const L: usize> BitSet<L> {
-    pub fn new() -> Self {
+pub(crate) fn is_zero(bits: &[Element]) -> bool {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    if bits.len() >= SIMD_THRESHOLD {
+        return crate::bitset_simd::is_zero(bits);
+    }
+
+    bits.iter().all(|bits| *bits == 0)
+}
+
+/// Returns true if every bit in `sub` is also in `sup`
+pub(crate) fn is_subset(sub: &[Element], sup: &[Element]) -> bool {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    if sub.len() >= SIMD_THRESHOLD {
+        return crate::bitset_simd::is_subset(sub, sup);
+    }
+
+    sub.iter().zip(sup).all(|(sub, sup)| sub & !sup == 0)
+}
+
+/// Returns true if no bit is in both `a` and `b`
+pub(crate) fn is_disjoint(a: &[Element], b: &[Element]) -> bool {
+    a.iter().zip(b).all(|(a, b)| a & b == 0)
+}
+
+/// Counts the bits in both `a` and `b`, without building their intersection
+pub(crate) fn intersection_count(a: &[Element], b: &[Element]) -> usize {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| (a & b).count_ones() as usize)
+        .sum()
+}
+
+/// Counts the bits in `a` and/or `b`, without building their union
+pub(crate) fn union_count(a: &[Element], b: &[Element]) -> usize {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| (a | b).count_ones() as usize)
+        .sum()
+}
+
+/// Counts the bits in `a` that aren't in `b`, without building their difference
+pub(crate) fn difference_count(a: &[Element], b: &[Element]) -> usize {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| (a & !b).count_ones() as usize)
+        .sum()
+}
+
+/// Shifts every bit towards the higher indices by `shift`, discarding any that are shifted past
+/// the last element. The caller is responsible for masking the tail afterwards.
+pub(crate) fn shl_assign(bits: &mut [Element], shift: usize) {
+    let (elements, offset) = (shift / BITS_PER_ELEMENT, shift % BITS_PER_ELEMENT);
+
+    // iterate backwards, so that each element is read before it is overwritten
+    for i in (0..bits.len()).rev() {
+        bits[i] = match i.checked_sub(elements) {
+            // the lower element carries the bits that are shifted out of the top of its neighbour
+            Some(src) if offset > 0 && src > 0 => {
+                bits[src] << offset | bits[src - 1] >> (BITS_PER_ELEMENT - offset)
+            }
+            Some(src) => bits[src] << offset,
+            None => 0,
+        };
+    }
+}
+
+/// Shifts every bit towards the lower indices by `shift`, discarding any that are shifted past the
+/// first element.
+pub(crate) fn shr_assign(bits: &mut [Element], shift: usize) {
+    let (elements, offset) = (shift / BITS_PER_ELEMENT, shift % BITS_PER_ELEMENT);
+    let len = bits.len();
+
+    // iterate forwards, so that each element is read before it is overwritten
+    for i in 0..len {
+        let src = i.saturating_add(elements);
+        bits[i] = if src >= len {
+            0
+        } else if offset > 0 && src + 1 < len {
+            // the upper element carries the bits that are shifted out of the bottom of its neighbour
+            bits[src] >> offset | bits[src + 1] << (BITS_PER_ELEMENT - offset)
+        } else {
+            bits[src] >> offset
+        };
+    }
+}
+
+pub(crate) fn count_ones(bits: &[Element]) -> usize {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    if bits.len() >= SIMD_THRESHOLD {
+        return crate::bitset_simd::count_ones(bits);
+    }
+
+    let mut total = 0;
+    for bits in bits.iter() {
+        total += bits.count_ones() as usize;
+    }
+    return total;
+}
+
+pub(crate) fn leading_zeros(bits: &[Element], len: usize) -> usize {
+    let mut result = 0;
+    for bits in bits.iter().rev() {
+        result += bits.leading_zeros() as usize;
+
+        if *bits > 0 {
+            break;
+        }
+    }
+    // the unused tail bits are always zero, so they are always counted
+    let padding = bits.len() * BITS_PER_ELEMENT - len;
+    return result - padding;
+}
+
+pub(crate) fn trailing_zeros(bits: &[Element], len: usize) -> usize {
+    let mut total = 0;
+    for bits in bits.iter() {
+        total += bits.trailing_zeros() as usize;
+
+        if *bits > 0 {
+            break;
+        }
+    }
+    // an empty set would otherwise count the unused tail bits too
+    return total.min(len);
+}
+
+pub(crate) fn next_one(bits: &[Element], len: usize, from: usize) -> Option<usize> {
+    if from >= len {
+        return None;
+    }
+
+    let mut i = from / BITS_PER_ELEMENT;
+    // ignore the bits before `from` in the first element
+    let mut current = bits[i] & (Element::MAX << (from % BITS_PER_ELEMENT));
+
+    loop {
+        if current != 0 {
+            return Some(i * BITS_PER_ELEMENT + current.trailing_zeros() as usize);
+        }
+
+        i += 1;
+        current = *bits.get(i)?;
+    }
+}
+
+pub(crate) fn next_zero(bits: &[Element], len: usize, from: usize) -> Option<usize> {
+    if from >= len {
+        return None;
+    }
+
+    let mut i = from / BITS_PER_ELEMENT;
+    // ignore the bits before `from` in the first element
+    let mut current = !bits[i] & (Element::MAX << (from % BITS_PER_ELEMENT));
+
+    loop {
+        if current != 0 {
+            let index = i * BITS_PER_ELEMENT + current.trailing_zeros() as usize;
+            // the unused tail bits are always clear, but are not part of the set
+            return (index < len).then_some(index);
+        }
+
+        i += 1;
+        current = !*bits.get(i)?;
+    }
+}
+
+pub(crate) fn prev_one(bits: &[Element], len: usize, from: usize) -> Option<usize> {
+    if len == 0 {
+        return None;
+    }
+
+    let from = from.min(len - 1);
+    let mut i = from / BITS_PER_ELEMENT;
+    // ignore the bits after `from` in the first element
+    let mut current = bits[i] & (Element::MAX >> (BITS_PER_ELEMENT - 1 - from % BITS_PER_ELEMENT));
+
+    loop {
+        if current != 0 {
+            let offset = BITS_PER_ELEMENT - 1 - current.leading_zeros() as usize;
+            return Some(i * BITS_PER_ELEMENT + offset);
+        }
+
+        i = i.checked_sub(1)?;
+        current = bits[i];
+    }
+}
+
+pub(crate) fn select(bits: &[Element], k: usize) -> Option<usize> {
+    let mut remaining = k;
+
+    for (i, bits) in bits.iter().enumerate() {
+        let count = bits.count_ones() as usize;
+
+        if remaining < count {
+            let mut bits = *bits;
+            // clear the lowest set bits until the one we want is the lowest
+            for _ in 0..remaining {
+                bits &= bits.wrapping_sub(1);
+            }
+
+            return Some(i * BITS_PER_ELEMENT + bits.trailing_zeros() as usize);
+        }
+
+        remaining -= count;
+    }
+
+    None
+}
+
+/// A bitset with a fixed length, configurable via const generics where `N` is the number of bits,
+/// and `L` is the number of `Element`s used to store them.
+///
+/// `L` must be equal to [words_for_bits] of `N`, which is checked at compile time. It only exists
+/// because stable Rust can't yet derive an array length from another const parameter.
+///
+/// Bits beyond `N` in the last element are always kept clear, so operations such as `Not` and
+/// `count_ones` only ever consider the logical width of the set.
+///
+/// Defaults to 64 bits, stored in 1 `Element`. The [bitset!] macro names the type from the number
+/// of bits alone.
+///
+/// Before version 2, the first parameter was the number of elements, so `BitSet<2>` was 128 bits
+/// rather than 2.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct BitSet<const N: usize = DEFAULT_CAPACITY, const L: usize = DEFAULT_ELEMENTS> {
+    pub(crate) bits: [Element; L],
+}
+
+impl<const N: usize, const L: usize> BitSet<N, L> {
+    pub const fn new() -> Self {
+        const {
+            assert!(
+                L == words_for_bits(N),
+                "`L` must be the number of elements needed to store `N` bits"
+            )
+        };
+
         Self { bits: [0; L] }
     }
 
-    pub fn from_index(index: usize) -> Self {
+    /// The number of bits that can be stored in this bitset
+    #[inline]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Clears any bits in the last element that are beyond the logical width of the set
+    #[inline]
+    pub(crate) fn mask_tail(&mut self) {
+        if let Some(last) = self.bits.last_mut() {
+            *last &= tail_mask(N);
+        }
+    }
+
+    /// # Panics
+    /// Panics if `index` is not less than the capacity of the bitset, which fails compilation when
+    /// evaluated in a const context
+    pub const fn from_index(index: usize) -> Self {
+        Self::from_indices(&[index])
+    }
+
+    /// Creates a bitset with the bits at each of `indices` set, e.g. to define masks as constants
+    ///
+    /// # Panics
+    /// Panics if any index is not less than the capacity of the bitset, which fails compilation
+    /// when evaluated in a const context
+    pub const fn from_indices(indices: &[usize]) -> Self {
         let mut bit_set = Self::new();
-        bit_set.set(index);
-        return bit_set;
+        let mut i = 0;
+        while i < indices.len() {
+            let index = indices[i];
+            // formatting the index into the message isn't possible in a const fn, so this only
+            // shares the start of the message with [BitIndexOutOfBounds]
+            assert!(
+                index < N,
+                "bit index is out of bounds: it must be less than the capacity of the bitset"
+            );
+            bit_set.bits[index / BITS_PER_ELEMENT] |= 1 << (index % BITS_PER_ELEMENT);
+            i += 1;
+        }
+        bit_set
+    }
+
+    /// Splits a bit index into the index of the element that holds it, and the bit offset within
+    /// that element.
+    ///
+    /// # Panics
+    /// Panics if `index` is not less than the capacity of the bitset
+    #[inline]
+    fn locate(&self, index: usize) -> (usize, usize) {
+        match self.try_locate(index) {
+            Ok(location) => location,
+            Err(error) => panic!("{error}"),
+        }
+    }
+
+    #[inline]
+    fn try_locate(&self, index: usize) -> Result<(usize, usize), BitIndexOutOfBounds> {
+        if index < self.capacity() {
+            Ok((index / BITS_PER_ELEMENT, index % BITS_PER_ELEMENT))
+        } else {
+            Err(BitIndexOutOfBounds {
+                index,
+                capacity: self.capacity(),
+            })
+        }
     }
 
+    /// # Panics
+    /// Panics if `index` is not less than the capacity of the bitset
     pub fn set(&mut self, index: usize) {
-        let (i, j) = (index / BITS_PER_ELEMENT, index % BITS_PER_ELEMENT);
+        let (i, j) = self.locate(index);
         self.bits[i] |= 1 << j;
     }
 
+    /// # Panics
+    /// Panics if `index` is not less than the capacity of the bitset
     pub fn clear(&mut self, index: usize) {
-        let (i, j) = (index / BITS_PER_ELEMENT, index % BITS_PER_ELEMENT);
+        let (i, j) = self.locate(index);
         self.bits[i] &= !(1 << j);
     }
 
+    /// # Panics
+    /// Panics if `index` is not less than the capacity of the bitset
     pub fn test(&self, index: usize) -> bool {
-        let (i, j) = (index / BITS_PER_ELEMENT, index % BITS_PER_ELEMENT);
+        let (i, j) = self.locate(index);
         self.bits[i] & (1 << j) != 0
     }
 
+    /// Sets the bit at `index`, or returns an error if it is out of bounds
+    pub fn try_set(&mut self, index: usize) -> Result<(), BitIndexOutOfBounds> {
+        let (i, j) = self.try_locate(index)?;
+        self.bits[i] |= 1 << j;
+        Ok(())
+    }
+
+    /// Clears the bit at `index`, or returns an error if it is out of bounds
+    pub fn try_clear(&mut self, index: usize) -> Result<(), BitIndexOutOfBounds> {
+        let (i, j) = self.try_locate(index)?;
+        self.bits[i] &= !(1 << j);
+        Ok(())
+    }
+
+    /// Returns whether the bit at `index` is set, or `None` if it is out of bounds
+    pub fn try_test(&self, index: usize) -> Option<bool> {
+        let (i, j) = self.try_locate(index).ok()?;
+        Some(self.bits[i] & (1 << j) != 0)
+    }
+
+    /// Flips the bit at `index`
+    ///
+    /// # Panics
+    /// Panics if `index` is not less than the capacity of the bitset
+    pub fn toggle(&mut self, index: usize) {
+        let (i, j) = self.locate(index);
+        self.bits[i] ^= 1 << j;
+    }
+
+    /// Sets every bit within `range`
+    ///
+    /// # Panics
+    /// Panics if the range is decreasing, or extends beyond the capacity of the bitset
+    pub fn set_range(&mut self, range: impl RangeBounds<usize>) {
+        for (i, mask) in range_masks(N, range) {
+            self.bits[i] |= mask;
+        }
+    }
+
+    /// Clears every bit within `range`
+    ///
+    /// # Panics
+    /// Panics if the range is decreasing, or extends beyond the capacity of the bitset
+    pub fn clear_range(&mut self, range: impl RangeBounds<usize>) {
+        for (i, mask) in range_masks(N, range) {
+            self.bits[i] &= !mask;
+        }
+    }
+
+    /// Flips every bit within `range`
+    ///
+    /// # Panics
+    /// Panics if the range is decreasing, or extends beyond the capacity of the bitset
+    pub fn flip_range(&mut self, range: impl RangeBounds<usize>) {
+        for (i, mask) in range_masks(N, range) {
+            self.bits[i] ^= mask;
+        }
+    }
+
+    /// Counts the set bits within `range`
+    ///
+    /// # Panics
+    /// Panics if the range is decreasing, or extends beyond the capacity of the bitset
+    pub fn count_ones_in(&self, range: impl RangeBounds<usize>) -> usize {
+        range_masks(N, range)
+            .map(|(i, mask)| (self.bits[i] & mask).count_ones() as usize)
+            .sum()
+    }
+
+    /// Returns true if any bit within `range` is set
+    ///
+    /// # Panics
+    /// Panics if the range is decreasing, or extends beyond the capacity of the bitset
+    pub fn any_in(&self, range: impl RangeBounds<usize>) -> bool {
+        range_masks(N, range).any(|(i, mask)| self.bits[i] & mask != 0)
+    }
+
     /// Returns true if other is a subset of self
     pub fn contains(&self, other: &Self) -> bool {
-        self | other == *self
+        is_subset(&other.bits, &self.bits)
+    }
+
+    /// The same as [BitSet::contains], for const contexts, without the SIMD kernels
+    pub const fn const_contains(&self, other: &Self) -> bool {
+        // accumulate rather than returning early, so that the loop can still be vectorised
+        let mut missing = 0;
+        let mut i = 0;
+        while i < L {
+            missing |= other.bits[i] & !self.bits[i];
+            i += 1;
+        }
+        missing == 0
     }
 
     pub fn contains_none(&self, other: &Self) -> bool {
-        (self & other).is_empty()
+        is_disjoint(&self.bits, &other.bits)
     }
 
     pub fn contains_some(&self, other: &Self) -> bool {
-        !(self & other).is_empty()
+        !is_disjoint(&self.bits, &other.bits)
+    }
+
+    /// Returns true if every bit in self is also in other
+    pub fn is_subset(&self, other: &Self) -> bool {
+        is_subset(&self.bits, &other.bits)
+    }
+
+    /// Returns true if every bit in other is also in self, the same as [BitSet::contains]
+    pub fn is_superset(&self, other: &Self) -> bool {
+        is_subset(&other.bits, &self.bits)
+    }
+
+    /// Returns true if self and other have no bits in common, the same as [BitSet::contains_none]
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        is_disjoint(&self.bits, &other.bits)
+    }
+
+    /// Returns the number of bits in self and other, i.e. `self.intersection(other).count_ones()`
+    pub fn intersection_count(&self, other: &Self) -> usize {
+        intersection_count(&self.bits, &other.bits)
+    }
+
+    /// Returns the number of bits in self and/or other, i.e. `self.union(other).count_ones()`
+    pub fn union_count(&self, other: &Self) -> usize {
+        union_count(&self.bits, &other.bits)
+    }
+
+    /// Returns the number of bits in self without bits in other, i.e.
+    /// `self.difference(other).count_ones()`
+    pub fn difference_count(&self, other: &Self) -> usize {
+        difference_count(&self.bits, &other.bits)
     }
 
     /// Returns bits that are in self, without bits in other
@@ -73,67 +604,355 @@ impl<const L: usize> BitSet<L> {
         self & other
     }
 
+    /// The same as [BitSet::intersection], for const contexts, without the SIMD kernels
+    pub const fn const_intersection(&self, other: &Self) -> Self {
+        let mut bits = self.bits;
+        let mut i = 0;
+        while i < L {
+            bits[i] &= other.bits[i];
+            i += 1;
+        }
+        Self { bits }
+    }
+
     /// Returns bits that are in self and/or other
     pub fn union(&self, other: &Self) -> Self {
         self | other
     }
 
+    /// The same as [BitSet::union], for const contexts, without the SIMD kernels
+    pub const fn const_union(&self, other: &Self) -> Self {
+        let mut bits = self.bits;
+        let mut i = 0;
+        while i < L {
+            bits[i] |= other.bits[i];
+            i += 1;
+        }
+        Self { bits }
+    }
+
     pub fn leading_zeros(&self) -> usize {
-        let mut result = 0;
-        for bits in self.bits.iter().rev() {
-            result += bits.leading_zeros() as usize;
+        leading_zeros(&self.bits, N)
+    }
 
-            if *bits > 0 {
-                break;
+    pub fn trailing_zeros(&self) -> usize {
+        trailing_zeros(&self.bits, N)
+    }
+
+    /// Returns the index of the lowest set bit
+    #[inline]
+    pub fn first_one(&self) -> Option<usize> {
+        self.next_one(0)
+    }
+
+    /// Returns the index of the highest set bit
+    #[inline]
+    pub fn last_one(&self) -> Option<usize> {
+        self.prev_one(usize::MAX)
+    }
+
+    /// Returns the index of the lowest clear bit
+    #[inline]
+    pub fn first_zero(&self) -> Option<usize> {
+        self.next_zero(0)
+    }
+
+    /// Returns the index of the lowest set bit that is at or after `from`
+    #[inline]
+    pub fn next_one(&self, from: usize) -> Option<usize> {
+        next_one(&self.bits, N, from)
+    }
+
+    /// Returns the index of the lowest clear bit that is at or after `from`
+    #[inline]
+    pub fn next_zero(&self, from: usize) -> Option<usize> {
+        next_zero(&self.bits, N, from)
+    }
+
+    /// Returns the index of the highest set bit that is at or before `from`
+    #[inline]
+    pub fn prev_one(&self, from: usize) -> Option<usize> {
+        prev_one(&self.bits, N, from)
+    }
+
+    /// Returns the number of set bits before `index`
+    ///
+    /// # Panics
+    /// Panics if `index` is greater than the capacity of the bitset
+    #[inline]
+    pub fn rank(&self, index: usize) -> usize {
+        self.count_ones_in(..index)
+    }
+
+    /// Returns the index of the `k`th set bit, counting from zero, so that `rank(select(k)) == k`
+    #[inline]
+    pub fn select(&self, k: usize) -> Option<usize> {
+        select(&self.bits, k)
+    }
+
+    pub fn count_ones(&self) -> usize {
+        count_ones(&self.bits)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        is_zero(&self.bits)
+    }
+
+    /// Rotates the bits towards the higher indices by `n`, wrapping the bits shifted past the end
+    /// of the set around to the start. This matches the direction of `u64::rotate_left`.
+    pub fn rotate_left(&self, n: usize) -> Self {
+        if N == 0 {
+            return self.clone();
+        }
+
+        let n = n % N;
+        // the right shift by `N` when `n` is zero leaves nothing behind, which is what we want
+        (self << n) | (self >> (N - n))
+    }
+
+    /// Rotates the bits towards the lower indices by `n`, wrapping the bits shifted past the start
+    /// of the set around to the end. This matches the direction of `u64::rotate_right`.
+    pub fn rotate_right(&self, n: usize) -> Self {
+        if N == 0 {
+            return self.clone();
+        }
+
+        self.rotate_left(N - n % N)
+    }
+
+    pub fn iter_indices(&self) -> SetBitsIter<'_> {
+        SetBitsIter::new(&self.bits)
+    }
+
+    /// The elements that store the bits, where bit `i` is bit `i % 64` of element `i / 64`
+    #[inline]
+    pub fn as_words(&self) -> &[u64; L] {
+        &self.bits
+    }
+
+    /// Creates a bitset from the elements that store the bits, as returned by
+    /// [as_words](Self::as_words), or returns an error if any bit at or beyond `N` is set.
+    pub fn from_words(words: [u64; L]) -> Result<Self, BitIndexOutOfBounds> {
+        let mut bit_set = Self::new();
+        bit_set.bits = words;
+
+        // find the first bit that would be lost by masking the tail
+        if let Some(&last) = bit_set.bits.last() {
+            let outside = last & !tail_mask(N);
+            if outside != 0 {
+                return Err(BitIndexOutOfBounds {
+                    index: (L - 1) * BITS_PER_ELEMENT + outside.trailing_zeros() as usize,
+                    capacity: N,
+                });
             }
         }
-        return result;
+
+        Ok(bit_set)
     }
 
-    pub fn trailing_zeros(&self) -> usize {
-        let mut total = 0;
-        for bits in self.bits.iter() {
-            total += bits.trailing_zeros() as usize;
+    /// Returns the bits as `N / 8` bytes (rounded up), in little endian order, where bit `i` is
+    /// bit `i % 8` of byte `i / 8`
+    pub fn to_le_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = self
+            .bits
+            .iter()
+            .flat_map(|bits| bits.to_le_bytes())
+            .collect();
+        bytes.truncate(N.div_ceil(8));
+        bytes
+    }
 
-            if *bits > 0 {
-                break;
+    /// Creates a bitset from bytes in the format of [to_le_bytes](Self::to_le_bytes). Any missing
+    /// bytes are treated as zero, but it is an error for any bit at or beyond `N` to be set.
+    pub fn from_le_bytes(bytes: &[u8]) -> Result<Self, BitIndexOutOfBounds> {
+        let mut words = [0; L];
+
+        for (i, chunk) in bytes.chunks(BITS_PER_ELEMENT / 8).enumerate() {
+            let mut word = [0; BITS_PER_ELEMENT / 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            let word = Element::from_le_bytes(word);
+
+            match words.get_mut(i) {
+                Some(bits) => *bits = word,
+                None if word == 0 => {}
+                None => {
+                    return Err(BitIndexOutOfBounds {
+                        index: i * BITS_PER_ELEMENT + word.trailing_zeros() as usize,
+                        capacity: N,
+                    })
+                }
             }
         }
-        return total;
+
+        Self::from_words(words)
     }
 
-    pub fn count_ones(&self) -> usize {
-        let mut total = 0;
-        for bits in self.bits.iter() {
-            total += bits.count_ones() as usize;
+    /// Encodes the indices of the set bits as LEB128 varints, each one storing the gap from the
+    /// previous index. This is far smaller than [to_le_bytes](Self::to_le_bytes) for mostly empty
+    /// sets, taking a single byte per index when they are close together.
+    pub fn to_sparse_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut next = 0;
+
+        for index in self.iter_indices() {
+            let mut gap = index - next;
+            next = index + 1;
+
+            loop {
+                let byte = (gap & 0x7F) as u8;
+                gap >>= 7;
+
+                if gap == 0 {
+                    bytes.push(byte);
+                    break;
+                }
+                // the high bit marks that more bytes follow
+                bytes.push(byte | 0x80);
+            }
         }
-        return total;
+
+        bytes
     }
 
-    pub fn is_empty(&self) -> bool {
-        self.bits.iter().all(|bits| *bits == 0)
+    /// Creates a bitset from bytes in the format of [to_sparse_bytes](Self::to_sparse_bytes)
+    pub fn from_sparse_bytes(bytes: &[u8]) -> Result<Self, DecodeBitSetError> {
+        let mut bit_set = Self::new();
+        let mut bytes = bytes.iter();
+        let mut next: usize = 0;
+
+        while bytes.len() > 0 {
+            let mut gap: usize = 0;
+            let mut shift = 0;
+
+            loop {
+                let byte = *bytes.next().ok_or(DecodeBitSetError::Truncated)?;
+                let value = (byte & 0x7F) as usize;
+
+                // any bits that don't fit in a usize are lost, so the varint is malformed
+                if shift >= usize::BITS || (value << shift) >> shift != value {
+                    return Err(DecodeBitSetError::InvalidVarint);
+                }
+                gap |= value << shift;
+                shift += 7;
+
+                if byte & 0x80 == 0 {
+                    break;
+                }
+            }
+
+            let index = next
+                .checked_add(gap)
+                .ok_or(DecodeBitSetError::InvalidVarint)?;
+            bit_set.try_set(index)?;
+            next = index + 1;
+        }
+
+        Ok(bit_set)
     }
+}
 
-    pub fn iter_indices(&self) -> SetBitsIter<L> {
-        let mut bit_slices = self.bits.iter();
-        let current_bits = *bit_slices.next().unwrap();
+/// Error returned when a bit index is not less than the capacity of a bitset
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BitIndexOutOfBounds {
+    pub index: usize,
+    pub capacity: usize,
+}
 
-        SetBitsIter {
-            bit_slices,
-            slice_index: 0,
-            current_bits,
+impl fmt::Display for BitIndexOutOfBounds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "bit index is out of bounds: the index is {} but the capacity is {}",
+            self.index, self.capacity
+        )
+    }
+}
+
+impl std::error::Error for BitIndexOutOfBounds {}
+
+/// Error returned when parsing a bitset from a string fails
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseBitSetError {
+    /// The string is not an index list, nor a binary or hexadecimal string
+    InvalidFormat,
+    /// A binary or hexadecimal string contains a character that is not a digit in its radix
+    InvalidDigit(char),
+    /// An index list contains an entry that is not an unsigned integer
+    InvalidIndex(ParseIntError),
+    /// The string refers to a bit that is beyond the capacity of the bitset
+    OutOfBounds(BitIndexOutOfBounds),
+}
+
+impl fmt::Display for ParseBitSetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidFormat => write!(f, "invalid bitset format"),
+            Self::InvalidDigit(digit) => write!(f, "invalid digit {digit:?} in bitset"),
+            Self::InvalidIndex(error) => write!(f, "invalid index in bitset: {error}"),
+            Self::OutOfBounds(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ParseBitSetError {}
+
+impl From<BitIndexOutOfBounds> for ParseBitSetError {
+    fn from(error: BitIndexOutOfBounds) -> Self {
+        Self::OutOfBounds(error)
+    }
+}
+
+/// Error returned when decoding a bitset from bytes fails
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecodeBitSetError {
+    /// The bytes end part way through an encoded value
+    Truncated,
+    /// An encoded value does not fit in a `usize`
+    InvalidVarint,
+    /// The bytes refer to a bit that is beyond the capacity of the bitset
+    OutOfBounds(BitIndexOutOfBounds),
+}
+
+impl fmt::Display for DecodeBitSetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Truncated => write!(f, "encoded bitset is truncated"),
+            Self::InvalidVarint => write!(f, "encoded bitset has an index that is too large"),
+            Self::OutOfBounds(error) => error.fmt(f),
         }
     }
 }
 
+impl std::error::Error for DecodeBitSetError {}
+
+impl From<BitIndexOutOfBounds> for DecodeBitSetError {
+    fn from(error: BitIndexOutOfBounds) -> Self {
+        Self::OutOfBounds(error)
+    }
+}
+
 /// Iterator over the indices of a bitset that are set to 1
-pub struct SetBitsIter<'a, const L: usize> {
+pub struct SetBitsIter<'a> {
     bit_slices: Iter<'a, Element>,
     slice_index: usize,
     current_bits: Element,
 }
 
-impl<'a, const L: usize> Iterator for SetBitsIter<'a, L> {
+impl<'a> SetBitsIter<'a> {
+    pub(crate) fn new(bits: &'a [Element]) -> Self {
+        let mut bit_slices = bits.iter();
+        // a zero length bitset has no elements at all, which is the same as having no set bits
+        let current_bits = bit_slices.next().copied().unwrap_or(0);
+
+        Self {
+            bit_slices,
+            slice_index: 0,
+            current_bits,
+        }
+    }
+}
+
+impl<'a> Iterator for SetBitsIter<'a> {
     type Item = usize;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -152,64 +971,347 @@ impl<'a, const L: usize> Iterator for SetBitsIter<'a, L> {
     }
 }
 
-impl<const L: usize> Default for BitSet<L> {
+/// Owning iterator over the indices of a bitset that are set to 1
+pub struct BitSetIntoIter<const L: usize> {
+    bits: [Element; L],
+    slice_index: usize,
+    current_bits: Element,
+}
+
+impl<const L: usize> Iterator for BitSetIntoIter<L> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // skip until we find an element that isn't 0
+        while self.current_bits == 0 {
+            self.slice_index += 1;
+            self.current_bits = *self.bits.get(self.slice_index)?;
+        }
+
+        let trailing_zeros = self.current_bits.trailing_zeros() as usize;
+
+        // clears the lowest significant bit
+        self.current_bits &= self.current_bits.wrapping_sub(1);
+
+        Some(self.slice_index * BITS_PER_ELEMENT + trailing_zeros)
+    }
+}
+
+impl<const N: usize, const L: usize> IntoIterator for BitSet<N, L> {
+    type Item = usize;
+    type IntoIter = BitSetIntoIter<L>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        BitSetIntoIter {
+            // a zero length bitset has no elements at all, which is the same as having no set bits
+            current_bits: self.bits.first().copied().unwrap_or(0),
+            bits: self.bits,
+            slice_index: 0,
+        }
+    }
+}
+
+impl<'a, const N: usize, const L: usize> IntoIterator for &'a BitSet<N, L> {
+    type Item = usize;
+    type IntoIter = SetBitsIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_indices()
+    }
+}
+
+/// # Panics
+/// Panics if any index is not less than the capacity of the bitset
+impl<const N: usize, const L: usize> Extend<usize> for BitSet<N, L> {
+    fn extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) {
+        for index in iter {
+            self.set(index);
+        }
+    }
+}
+
+/// # Panics
+/// Panics if any index is not less than the capacity of the bitset
+impl<const N: usize, const L: usize> FromIterator<usize> for BitSet<N, L> {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut bit_set = Self::new();
+        bit_set.extend(iter);
+        bit_set
+    }
+}
+
+/// Formats the bitset as the set of indices that are set to 1, e.g. `{3, 17, 64}`
+impl<const N: usize, const L: usize> fmt::Debug for BitSet<N, L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter_indices()).finish()
+    }
+}
+
+/// Formats the bitset as `N` binary digits, with the highest index first, as you would read an
+/// integer. The alternate flag (`{:#b}`) adds a `0b` prefix.
+impl<const N: usize, const L: usize> fmt::Binary for BitSet<N, L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits: String = (0..N)
+            .rev()
+            .map(|i| if self.test(i) { '1' } else { '0' })
+            .collect();
+
+        f.pad_integral(true, "0b", &digits)
+    }
+}
+
+/// Formats the bitset as enough hexadecimal digits to hold `N` bits, with the highest index
+/// first, as you would read an integer. The alternate flag (`{:#x}`) adds a `0x` prefix.
+///
+/// Without the prefix, the digits are parsed back with [BitSet::from_str_radix], as `FromStr`
+/// would read them as binary.
+impl<const N: usize, const L: usize> fmt::LowerHex for BitSet<N, L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const BITS_PER_DIGIT: usize = 4;
+
+        let digits: String = (0..N.div_ceil(BITS_PER_DIGIT))
+            .rev()
+            .map(|digit| {
+                let index = digit * BITS_PER_DIGIT;
+                // a digit never straddles two elements, as the element size is a multiple of 4
+                let (i, j) = (index / BITS_PER_ELEMENT, index % BITS_PER_ELEMENT);
+                let nibble = (self.bits[i] >> j) & 0xF;
+
+                char::from_digit(nibble as u32, 16).unwrap()
+            })
+            .collect();
+
+        f.pad_integral(true, "0x", &digits)
+    }
+}
+
+/// Parses any of the formats that a bitset can be formatted as:
+/// - An index list, as produced by `Debug`, e.g. `{3, 17, 64}`.
+/// - A binary string, as produced by `Binary`, with an optional `0b` prefix.
+/// - A hexadecimal string, as produced by `LowerHex` with the alternate flag (`{:#x}`), with a
+///   mandatory `0x` prefix, as digits without a prefix are read as binary. Use
+///   [BitSet::from_str_radix] for hexadecimal digits without a prefix.
+///
+/// Binary and hexadecimal strings may have fewer digits than the bitset (missing digits are 0), and
+/// may contain `_` separators. An empty string, as produced by `Binary` for a bitset with no bits,
+/// is the empty set.
+impl<const N: usize, const L: usize> FromStr for BitSet<N, L> {
+    type Err = ParseBitSetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        if let Some(list) = s.strip_prefix('{') {
+            let list = list
+                .strip_suffix('}')
+                .ok_or(ParseBitSetError::InvalidFormat)?;
+            let mut bit_set = Self::new();
+
+            // allows for the trailing comma of the pretty printed format
+            let list = list.trim_end();
+            let list = list.strip_suffix(',').unwrap_or(list);
+            if list.trim().is_empty() {
+                return Ok(bit_set);
+            }
+
+            for entry in list.split(',') {
+                let index = entry
+                    .trim()
+                    .parse()
+                    .map_err(ParseBitSetError::InvalidIndex)?;
+                bit_set.try_set(index)?;
+            }
+
+            return Ok(bit_set);
+        }
+
+        match s.strip_prefix("0x") {
+            Some(digits) => Self::from_str_radix(digits, 16),
+            None => Self::from_str_radix(s.strip_prefix("0b").unwrap_or(s), 2),
+        }
+    }
+}
+
+impl<const N: usize, const L: usize> BitSet<N, L> {
+    /// Parses binary or hexadecimal digits without a prefix, such as the output of `{:b}` or
+    /// `{:x}`, with the highest index first. The digits may contain `_` separators, and may be
+    /// fewer than the bitset (missing digits are 0), or none at all.
+    ///
+    /// # Panics
+    /// Panics if `radix` is not 2 or 16
+    pub fn from_str_radix(digits: &str, radix: u32) -> Result<Self, ParseBitSetError> {
+        assert!(
+            radix == 2 || radix == 16,
+            "a bitset can only be parsed from binary or hexadecimal digits"
+        );
+        let bits_per_digit = radix.trailing_zeros() as usize;
+
+        let mut bit_set = Self::new();
+        let digits = digits.chars().rev().filter(|c| *c != '_');
+
+        for (position, c) in digits.enumerate() {
+            let value = c.to_digit(radix).ok_or(ParseBitSetError::InvalidDigit(c))?;
+
+            for bit in 0..bits_per_digit {
+                if value & (1 << bit) != 0 {
+                    bit_set.try_set(position * bits_per_digit + bit)?;
+                }
+            }
+        }
+
+        Ok(bit_set)
+    }
+}
+
+/// Bitsets are ordered as if they were unsigned integers, so the set with the highest bit that
+/// isn't in the other is greater, e.g. `{0, 1, 2} < {3}`, and the empty set is the least of all.
+impl<const N: usize, const L: usize> Ord for BitSet<N, L> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.bits.iter().rev().cmp(other.bits.iter().rev())
+    }
+}
+
+impl<const N: usize, const L: usize> PartialOrd for BitSet<N, L> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<const N: usize, const L: usize> Default for BitSet<N, L> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<const L: usize> Not for &BitSet<L> {
-    type Output = BitSet<L>;
+impl<const N: usize, const L: usize> Not for BitSet<N, L> {
+    type Output = BitSet<N, L>;
+
+    fn not(mut self) -> Self::Output {
+        not_assign(&mut self.bits);
+        self.mask_tail();
+        self
+    }
+}
+
+impl<const N: usize, const L: usize> Not for &BitSet<N, L> {
+    type Output = BitSet<N, L>;
 
     fn not(self) -> Self::Output {
-        let bits = core::array::from_fn(|i| !self.bits[i]);
-        BitSet { bits }
+        !self.clone()
     }
 }
 
 macro_rules! impl_bitwise_assign {
-    ( $trait:ident, $fn:ident, $op:tt ) => {
-
-        impl<const L: usize> $trait<&BitSet<L>> for BitSet<L> {
-            fn $fn(&mut self, rhs: &BitSet<L>) {
-                for i in 0..L {
-                    self.bits[i] $op rhs.bits[i];
-                }
+    ( $trait:ident, $fn:ident, $kernel:ident ) => {
+        impl<const N: usize, const L: usize> $trait for BitSet<N, L> {
+            fn $fn(&mut self, rhs: BitSet<N, L>) {
+                $kernel(&mut self.bits, &rhs.bits);
             }
         }
 
-        impl<const L: usize> $trait<&mut BitSet<L>> for BitSet<L> {
-            fn $fn(&mut self, rhs: &mut BitSet<L>) {
-                for i in 0..L {
-                    self.bits[i] $op rhs.bits[i];
-                }
+        impl<const N: usize, const L: usize> $trait<&BitSet<N, L>> for BitSet<N, L> {
+            fn $fn(&mut self, rhs: &BitSet<N, L>) {
+                $kernel(&mut self.bits, &rhs.bits);
             }
         }
 
+        impl<const N: usize, const L: usize> $trait<&mut BitSet<N, L>> for BitSet<N, L> {
+            fn $fn(&mut self, rhs: &mut BitSet<N, L>) {
+                $kernel(&mut self.bits, &rhs.bits);
+            }
+        }
     };
 }
 
 macro_rules! impl_bitwise {
-    ( $trait:ident, $fn:ident, $op:tt ) => {
+    ( $trait:ident, $fn:ident, $kernel:ident ) => {
+        impl<const N: usize, const L: usize> $trait for BitSet<N, L> {
+            type Output = BitSet<N, L>;
+
+            fn $fn(mut self, rhs: Self) -> Self::Output {
+                $kernel(&mut self.bits, &rhs.bits);
+                self
+            }
+        }
+
+        impl<const N: usize, const L: usize> $trait<&BitSet<N, L>> for BitSet<N, L> {
+            type Output = BitSet<N, L>;
+
+            fn $fn(mut self, rhs: &BitSet<N, L>) -> Self::Output {
+                $kernel(&mut self.bits, &rhs.bits);
+                self
+            }
+        }
 
-        impl<const L: usize> $trait for &BitSet<L> {
-            type Output = BitSet<L>;
+        impl<const N: usize, const L: usize> $trait<BitSet<N, L>> for &BitSet<N, L> {
+            type Output = BitSet<N, L>;
+
+            fn $fn(self, mut rhs: BitSet<N, L>) -> Self::Output {
+                // all of these operations are commutative, so the owned side can be reused
+                $kernel(&mut rhs.bits, &self.bits);
+                rhs
+            }
+        }
+
+        impl<const N: usize, const L: usize> $trait for &BitSet<N, L> {
+            type Output = BitSet<N, L>;
 
             fn $fn(self, rhs: Self) -> Self::Output {
-                let bits = core::array::from_fn(|i| self.bits[i] $op rhs.bits[i]);
-                BitSet { bits }
+                let mut result = self.clone();
+                $kernel(&mut result.bits, &rhs.bits);
+                result
+            }
+        }
+    };
+}
+
+impl_bitwise!(BitAnd, bitand, and_assign);
+impl_bitwise!(BitOr, bitor, or_assign);
+impl_bitwise!(BitXor, bitxor, xor_assign);
+impl_bitwise_assign!(BitAndAssign, bitand_assign, and_assign);
+impl_bitwise_assign!(BitOrAssign, bitor_assign, or_assign);
+impl_bitwise_assign!(BitXorAssign, bitxor_assign, xor_assign);
+
+impl<const N: usize, const L: usize> ShlAssign<usize> for BitSet<N, L> {
+    /// Shifts every bit towards the higher indices, discarding any that are shifted past the end
+    fn shl_assign(&mut self, rhs: usize) {
+        shl_assign(&mut self.bits, rhs);
+        self.mask_tail();
+    }
+}
+
+impl<const N: usize, const L: usize> ShrAssign<usize> for BitSet<N, L> {
+    /// Shifts every bit towards the lower indices, discarding any that are shifted past the start
+    fn shr_assign(&mut self, rhs: usize) {
+        shr_assign(&mut self.bits, rhs);
+    }
+}
+
+macro_rules! impl_shift {
+    ( $trait:ident, $fn:ident, $op:tt ) => {
+        impl<const N: usize, const L: usize> $trait<usize> for BitSet<N, L> {
+            type Output = BitSet<N, L>;
+
+            fn $fn(mut self, rhs: usize) -> Self::Output {
+                self $op rhs;
+                self
             }
         }
 
+        impl<const N: usize, const L: usize> $trait<usize> for &BitSet<N, L> {
+            type Output = BitSet<N, L>;
+
+            fn $fn(self, rhs: usize) -> Self::Output {
+                let mut result = self.clone();
+                result $op rhs;
+                result
+            }
+        }
     };
 }
 
-impl_bitwise!(BitAnd, bitand, &);
-impl_bitwise!(BitOr, bitor, |);
-impl_bitwise!(BitXor, bitxor, ^);
-impl_bitwise_assign!(BitAndAssign, bitand_assign, &=);
-impl_bitwise_assign!(BitOrAssign, bitor_assign, |=);
-impl_bitwise_assign!(BitXorAssign, bitxor_assign, ^=);
+impl_shift!(Shl, shl, <<=);
+impl_shift!(Shr, shr, >>=);
 
 #[cfg(test)]
 mod tests {
@@ -217,13 +1319,13 @@ mod tests {
 
     #[test]
     fn test_bitset() {
-        let mut bitset1 = BitSet::<1>::new();
+        let mut bitset1 = BitSet::<64>::new();
         bitset1.set(17);
         assert!(bitset1.test(17));
         bitset1.clear(17);
         assert!(!bitset1.test(17));
 
-        let mut bitset2 = BitSet::<2>::new();
+        let mut bitset2 = BitSet::<128, 2>::new();
         bitset2.set(17);
         bitset2.set(BITS_PER_ELEMENT + 5);
         assert!(bitset2.test(17));
@@ -232,11 +1334,11 @@ mod tests {
 
     #[test]
     fn test_bitset_and() {
-        let mut bitset1 = BitSet::<2>::new();
+        let mut bitset1 = BitSet::<128, 2>::new();
         bitset1.set(17);
         bitset1.set(63);
 
-        let mut bitset2 = BitSet::<2>::new();
+        let mut bitset2 = BitSet::<128, 2>::new();
         bitset2.set(17);
         bitset2.set(BITS_PER_ELEMENT + 5);
 
@@ -249,11 +1351,11 @@ mod tests {
 
     #[test]
     fn test_bitset_or() {
-        let mut bitset1 = BitSet::<2>::new();
+        let mut bitset1 = BitSet::<128, 2>::new();
         bitset1.set(17);
         bitset1.set(63);
 
-        let mut bitset2 = BitSet::<2>::new();
+        let mut bitset2 = BitSet::<128, 2>::new();
         bitset2.set(17);
         bitset2.set(BITS_PER_ELEMENT + 5);
 
@@ -266,11 +1368,11 @@ mod tests {
 
     #[test]
     fn test_bitset_xor() {
-        let mut bitset1 = BitSet::<2>::new();
+        let mut bitset1 = BitSet::<128, 2>::new();
         bitset1.set(17);
         bitset1.set(63);
 
-        let mut bitset2 = BitSet::<2>::new();
+        let mut bitset2 = BitSet::<128, 2>::new();
         bitset2.set(17);
         bitset2.set(BITS_PER_ELEMENT + 5);
 
@@ -283,7 +1385,7 @@ mod tests {
 
     #[test]
     fn test_bitset_not() {
-        let mut bitset1 = BitSet::<2>::new();
+        let mut bitset1 = BitSet::<128, 2>::new();
         bitset1.set(17);
         bitset1.set(63);
 
@@ -296,7 +1398,7 @@ mod tests {
 
     #[test]
     fn test_bitset_index_iter() {
-        let mut bitset1 = BitSet::<2>::new();
+        let mut bitset1 = BitSet::<128, 2>::new();
         bitset1.set(17);
         bitset1.set(63);
         bitset1.set(BITS_PER_ELEMENT + 5);
@@ -307,19 +1409,19 @@ mod tests {
         assert_eq!(iter.next(), Some(BITS_PER_ELEMENT + 5));
         assert_eq!(iter.next(), None);
 
-        let bitset2 = BitSet::<2>::new();
+        let bitset2 = BitSet::<128, 2>::new();
         let mut iter = bitset2.iter_indices();
         assert_eq!(iter.next(), None);
     }
 
     #[test]
     fn test_bitset_trailing_zeros() {
-        let mut bitset1 = BitSet::<1>::new();
+        let mut bitset1 = BitSet::<64>::new();
         bitset1.set(17);
         bitset1.set(63);
         assert_eq!(bitset1.trailing_zeros(), 17);
 
-        let mut bitset2 = BitSet::<2>::new();
+        let mut bitset2 = BitSet::<128, 2>::new();
         bitset2.set(17);
         bitset2.set(BITS_PER_ELEMENT + 5);
         assert_eq!(bitset2.trailing_zeros(), 17);
@@ -327,12 +1429,12 @@ mod tests {
 
     #[test]
     fn test_bitset_leading_zeros() {
-        let mut bitset1 = BitSet::<1>::new();
+        let mut bitset1 = BitSet::<64>::new();
         bitset1.set(17);
         bitset1.set(63);
         assert_eq!(bitset1.leading_zeros(), BITS_PER_ELEMENT - (63 + 1));
 
-        let mut bitset2 = BitSet::<2>::new();
+        let mut bitset2 = BitSet::<128, 2>::new();
         bitset2.set(17);
         bitset2.set(BITS_PER_ELEMENT + 5);
         assert_eq!(bitset2.leading_zeros(), BITS_PER_ELEMENT - (5 + 1));
@@ -340,14 +1442,677 @@ mod tests {
 
     #[test]
     fn test_bitset_count_ones() {
-        let mut bitset1 = BitSet::<1>::new();
+        let mut bitset1 = BitSet::<64>::new();
         bitset1.set(17);
         bitset1.set(63);
         assert_eq!(bitset1.count_ones(), 2);
 
-        let mut bitset2 = BitSet::<2>::new();
+        let mut bitset2 = BitSet::<128, 2>::new();
         bitset2.set(17);
         bitset2.set(BITS_PER_ELEMENT + 5);
         assert_eq!(bitset2.count_ones(), 2);
     }
+
+    #[test]
+    fn test_bitset_checked() {
+        let mut bitset = BitSet::<128, 2>::new();
+        assert_eq!(bitset.capacity(), 2 * BITS_PER_ELEMENT);
+
+        assert_eq!(bitset.try_set(127), Ok(()));
+        assert_eq!(bitset.try_test(127), Some(true));
+        assert_eq!(bitset.try_clear(127), Ok(()));
+        assert_eq!(bitset.try_test(127), Some(false));
+
+        let error = BitIndexOutOfBounds {
+            index: 128,
+            capacity: 128,
+        };
+        assert_eq!(bitset.try_set(128), Err(error));
+        assert_eq!(bitset.try_clear(128), Err(error));
+        assert_eq!(bitset.try_test(128), None);
+        assert!(bitset.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "bit index is out of bounds: the index is 64 but the capacity is 64")]
+    fn test_bitset_set_out_of_bounds() {
+        BitSet::<64>::new().set(64);
+    }
+
+    #[test]
+    fn test_bitset_zero_length() {
+        let mut bitset = BitSet::<0, 0>::new();
+        assert_eq!(bitset.capacity(), 0);
+        assert!(bitset.is_empty());
+        assert_eq!(bitset.count_ones(), 0);
+        assert_eq!(bitset.leading_zeros(), 0);
+        assert_eq!(bitset.trailing_zeros(), 0);
+        assert_eq!(bitset.iter_indices().next(), None);
+        assert_eq!(
+            bitset.try_set(0),
+            Err(BitIndexOutOfBounds {
+                index: 0,
+                capacity: 0
+            })
+        );
+        assert_eq!(bitset.try_test(0), None);
+
+        let other = BitSet::<0, 0>::new();
+        assert!(bitset.contains(&other));
+        assert!(bitset.contains_none(&other));
+        assert!((!&bitset).is_empty());
+    }
+
+    #[test]
+    fn test_bitset_partial_element() {
+        let mut bitset = BitSet::<100, { words_for_bits(100) }>::new();
+        assert_eq!(bitset.capacity(), 100);
+        assert_eq!(bitset.leading_zeros(), 100);
+        assert_eq!(bitset.trailing_zeros(), 100);
+
+        bitset.set(99);
+        assert_eq!(bitset.leading_zeros(), 0);
+        assert_eq!(bitset.trailing_zeros(), 99);
+        assert_eq!(
+            bitset.try_set(100),
+            Err(BitIndexOutOfBounds {
+                index: 100,
+                capacity: 100
+            })
+        );
+
+        let inverted = !&bitset;
+        assert_eq!(inverted.count_ones(), 99);
+        assert_eq!(inverted.leading_zeros(), 1);
+        assert_eq!(inverted.iter_indices().last(), Some(98));
+        assert_eq!((!&inverted), bitset);
+    }
+
+    /// Checks every range of a bitset against a naive, bit by bit, implementation
+    fn check_ranges<const N: usize, const L: usize>() {
+        // a pattern that differs across element boundaries
+        let mut pattern = BitSet::<N, L>::new();
+        for i in (0..N).filter(|i| i % 3 == 0 || i % 7 == 1) {
+            pattern.set(i);
+        }
+
+        for start in 0..=N {
+            for end in start..=N {
+                let mut set = BitSet::<N, L>::new();
+                set.set_range(start..end);
+                for i in 0..N {
+                    assert_eq!(set.test(i), (start..end).contains(&i));
+                }
+
+                let mut cleared = !&BitSet::<N, L>::new();
+                cleared.clear_range(start..end);
+                assert_eq!(cleared, !&set);
+
+                let mut flipped = pattern.clone();
+                flipped.flip_range(start..end);
+                assert_eq!(flipped, &pattern ^ &set);
+
+                let naive_count = (start..end).filter(|&i| pattern.test(i)).count();
+                assert_eq!(pattern.count_ones_in(start..end), naive_count);
+                assert_eq!(pattern.any_in(start..end), naive_count > 0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_bitset_ranges() {
+        check_ranges::<0, 0>();
+        check_ranges::<64, 1>();
+        check_ranges::<130, 3>();
+        check_ranges::<192, 3>();
+    }
+
+    #[test]
+    fn test_bitset_range_bounds() {
+        let mut bitset = BitSet::<128, 2>::new();
+        bitset.set_range(60..=67);
+        assert_eq!(bitset.count_ones(), 8);
+        assert_eq!(bitset.count_ones_in(..64), 4);
+        assert_eq!(bitset.count_ones_in(64..), 4);
+        assert_eq!(bitset.count_ones_in(..), 8);
+
+        bitset.clear_range(..);
+        assert!(bitset.is_empty());
+
+        bitset.toggle(100);
+        assert!(bitset.test(100));
+        bitset.toggle(100);
+        assert!(!bitset.test(100));
+    }
+
+    #[test]
+    #[should_panic(expected = "range end 129 is out of bounds for a bitset with capacity 128")]
+    fn test_bitset_range_out_of_bounds() {
+        BitSet::<128, 2>::new().set_range(0..129);
+    }
+
+    #[test]
+    fn test_bitset_search() {
+        let mut bitset = BitSet::<130, 3>::new();
+        for i in (0..130).filter(|i| i % 3 == 0 || i % 7 == 1) {
+            bitset.set(i);
+        }
+        let ones: Vec<usize> = bitset.iter_indices().collect();
+
+        for from in 0..=130 {
+            let naive_next_one = (from..130).find(|&i| bitset.test(i));
+            let naive_next_zero = (from..130).find(|&i| !bitset.test(i));
+            let naive_prev_one = (0..=from.min(129)).rev().find(|&i| bitset.test(i));
+
+            assert_eq!(bitset.next_one(from), naive_next_one);
+            assert_eq!(bitset.next_zero(from), naive_next_zero);
+            assert_eq!(bitset.prev_one(from), naive_prev_one);
+            assert_eq!(
+                bitset.rank(from),
+                ones.iter().filter(|&&i| i < from).count()
+            );
+        }
+
+        for (k, &index) in ones.iter().enumerate() {
+            assert_eq!(bitset.select(k), Some(index));
+            assert_eq!(bitset.rank(index), k);
+        }
+        assert_eq!(bitset.select(ones.len()), None);
+
+        assert_eq!(bitset.first_one(), Some(0));
+        assert_eq!(bitset.last_one(), Some(129));
+        assert_eq!(bitset.first_zero(), Some(2));
+    }
+
+    #[test]
+    fn test_bitset_search_edges() {
+        let empty = BitSet::<100, 2>::new();
+        assert_eq!(empty.first_one(), None);
+        assert_eq!(empty.last_one(), None);
+        assert_eq!(empty.first_zero(), Some(0));
+        assert_eq!(empty.select(0), None);
+
+        let full = !&empty;
+        assert_eq!(full.first_zero(), None);
+        assert_eq!(full.next_zero(64), None);
+        assert_eq!(full.last_one(), Some(99));
+        assert_eq!(full.select(99), Some(99));
+        assert_eq!(full.rank(100), 100);
+
+        let zero = BitSet::<0, 0>::new();
+        assert_eq!(zero.first_one(), None);
+        assert_eq!(zero.last_one(), None);
+        assert_eq!(zero.first_zero(), None);
+        assert_eq!(zero.rank(0), 0);
+    }
+
+    #[test]
+    fn test_bitset_wide_ops() {
+        // wide enough that the SIMD kernels are used, when enabled, with a partial vector at the end
+        let mut a = BitSet::<1000, { words_for_bits(1000) }>::new();
+        let mut b = BitSet::<1000, { words_for_bits(1000) }>::new();
+        for i in (0..1000).step_by(3) {
+            a.set(i);
+        }
+        for i in (0..1000).step_by(5) {
+            b.set(i);
+        }
+
+        let and = &a & &b;
+        let or = &a | &b;
+        let xor = &a ^ &b;
+        let not = !&a;
+        for i in 0..1000 {
+            assert_eq!(and.test(i), i % 3 == 0 && i % 5 == 0);
+            assert_eq!(or.test(i), i % 3 == 0 || i % 5 == 0);
+            assert_eq!(xor.test(i), (i % 3 == 0) != (i % 5 == 0));
+            assert_eq!(not.test(i), i % 3 != 0);
+        }
+
+        assert_eq!(a.count_ones(), 334);
+        assert_eq!(not.count_ones(), 666);
+        assert!(or.contains(&a));
+        assert!(!a.contains(&or));
+        assert!(!a.is_empty());
+        assert!((&a & &not).is_empty());
+
+        let mut assigned = a.clone();
+        assigned |= &b;
+        assert_eq!(assigned, or);
+        assigned &= &b;
+        assert_eq!(assigned, b);
+        assigned ^= &b;
+        assert!(assigned.is_empty());
+    }
+
+    #[test]
+    fn test_bitset_owned_ops() {
+        let a: BitSet<130, 3> = [1, 64, 129].into_iter().collect();
+        let b: BitSet<130, 3> = [1, 65, 128].into_iter().collect();
+
+        assert_eq!(a.clone() & b.clone(), &a & &b);
+        assert_eq!(a.clone() | &b, &a | &b);
+        assert_eq!(&a ^ b.clone(), &a ^ &b);
+        assert_eq!(!a.clone(), !&a);
+
+        let mut assigned = a.clone();
+        assigned |= b.clone();
+        assert_eq!(
+            assigned.iter_indices().collect::<Vec<_>>(),
+            vec![1, 64, 65, 128, 129]
+        );
+        assigned ^= b.clone();
+        assert_eq!(assigned, a.difference(&b));
+    }
+
+    #[test]
+    fn test_bitset_shifts() {
+        let indices = [0, 1, 63, 64, 100, 129];
+        let bitset: BitSet<130, 3> = indices.into_iter().collect();
+
+        for shift in 0..=200 {
+            let expected: Vec<usize> = indices
+                .iter()
+                .map(|i| i + shift)
+                .filter(|&i| i < 130)
+                .collect();
+            assert_eq!(
+                (&bitset << shift).iter_indices().collect::<Vec<_>>(),
+                expected
+            );
+
+            let expected: Vec<usize> = indices
+                .iter()
+                .filter_map(|i| i.checked_sub(shift))
+                .collect();
+            assert_eq!(
+                (&bitset >> shift).iter_indices().collect::<Vec<_>>(),
+                expected
+            );
+
+            let mut expected: Vec<usize> = indices.iter().map(|i| (i + shift) % 130).collect();
+            expected.sort();
+            assert_eq!(
+                bitset.rotate_left(shift).iter_indices().collect::<Vec<_>>(),
+                expected
+            );
+            assert_eq!(bitset.rotate_left(shift).rotate_right(shift), bitset);
+        }
+
+        let mut shifted = bitset.clone();
+        shifted <<= 1;
+        shifted >>= 1;
+        // the top bit was shifted out, and doesn't come back
+        assert_eq!(
+            shifted.iter_indices().collect::<Vec<_>>(),
+            vec![0, 1, 63, 64, 100]
+        );
+        assert_eq!((bitset.clone() << usize::MAX).count_ones(), 0);
+        assert_eq!((bitset >> usize::MAX).count_ones(), 0);
+
+        let empty = BitSet::<0, 0>::new();
+        assert_eq!(empty.rotate_left(3), empty);
+    }
+
+    #[test]
+    fn test_bitset_iterators() {
+        let mut bitset: BitSet<130, 3> = [129, 3, 64].into_iter().collect();
+        bitset.extend([5, 3]);
+
+        assert_eq!(
+            (&bitset).into_iter().collect::<Vec<_>>(),
+            vec![3, 5, 64, 129]
+        );
+        assert_eq!(bitset.into_iter().collect::<Vec<_>>(), vec![3, 5, 64, 129]);
+
+        assert_eq!(BitSet::<0, 0>::new().into_iter().next(), None);
+        assert_eq!(BitSet::<130, 3>::new().into_iter().next(), None);
+    }
+
+    #[test]
+    fn test_bitset_debug() {
+        let bitset: BitSet<130, 3> = [3, 17, 64, 129].into_iter().collect();
+        assert_eq!(format!("{:?}", bitset), "{3, 17, 64, 129}");
+        assert_eq!(format!("{:?}", BitSet::<130, 3>::new()), "{}");
+
+        assert_eq!(
+            format!("{:?}", bitset).parse::<BitSet<130, 3>>(),
+            Ok(bitset.clone())
+        );
+        assert_eq!(
+            format!("{:#?}", bitset).parse::<BitSet<130, 3>>(),
+            Ok(bitset.clone())
+        );
+        assert_eq!("{}".parse::<BitSet<130, 3>>(), Ok(BitSet::new()));
+        assert_eq!(" { 3,17 , 64,129 } ".parse::<BitSet<130, 3>>(), Ok(bitset));
+    }
+
+    #[test]
+    fn test_bitset_binary_hex() {
+        let bitset: BitSet<10, 1> = [0, 3, 9].into_iter().collect();
+        assert_eq!(format!("{:b}", bitset), "1000001001");
+        assert_eq!(format!("{:#b}", bitset), "0b1000001001");
+        assert_eq!(format!("{:x}", bitset), "209");
+        assert_eq!(format!("{:#x}", bitset), "0x209");
+        assert_eq!(format!("{:>6x}", bitset), "   209");
+
+        assert_eq!("1000001001".parse::<BitSet<10, 1>>(), Ok(bitset.clone()));
+        assert_eq!(
+            "0b10_0000_1001".parse::<BitSet<10, 1>>(),
+            Ok(bitset.clone())
+        );
+        assert_eq!("0x209".parse::<BitSet<10, 1>>(), Ok(bitset.clone()));
+        assert_eq!("0x0209".parse::<BitSet<10, 1>>(), Ok(bitset.clone()));
+        assert_eq!("0b1".parse::<BitSet<10, 1>>(), Ok(BitSet::from_index(0)));
+
+        assert_eq!(format!("{:b}", BitSet::<0, 0>::new()), "");
+
+        assert_eq!(BitSet::from_str_radix("209", 16), Ok(bitset.clone()));
+        assert_eq!(BitSet::from_str_radix("10_0000_1001", 2), Ok(bitset));
+        assert_eq!(
+            BitSet::<10, 1>::from_str_radix("0x209", 16),
+            Err(ParseBitSetError::InvalidDigit('x'))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "a bitset can only be parsed from binary or hexadecimal digits")]
+    fn test_bitset_from_str_radix_invalid_radix() {
+        let _ = BitSet::<10, 1>::from_str_radix("7", 8);
+    }
+
+    #[test]
+    fn test_bitset_format_round_trip() {
+        let bitset: BitSet<200, 4> = [0, 1, 63, 64, 65, 127, 128, 150, 199].into_iter().collect();
+
+        for formatted in [
+            format!("{:?}", bitset),
+            format!("{:#?}", bitset),
+            format!("{:b}", bitset),
+            format!("{:#b}", bitset),
+            format!("{:#x}", bitset),
+        ] {
+            assert_eq!(
+                formatted.parse::<BitSet<200, 4>>(),
+                Ok(bitset.clone()),
+                "{formatted}"
+            );
+        }
+
+        // the unprefixed output of the formatters, as is
+        let hex = format!("{:x}", bitset);
+        assert_eq!(hex.len(), 50);
+        assert_eq!(BitSet::from_str_radix(&hex, 16), Ok(bitset.clone()));
+        let binary = format!("{:b}", bitset);
+        assert_eq!(BitSet::from_str_radix(&binary, 2), Ok(bitset));
+
+        // a bitset with no bits formats as no digits at all
+        let empty = BitSet::<0, 0>::new();
+        for formatted in [
+            format!("{:b}", empty),
+            format!("{:#b}", empty),
+            format!("{:#x}", empty),
+        ] {
+            assert_eq!(
+                formatted.parse::<BitSet<0, 0>>(),
+                Ok(empty.clone()),
+                "{formatted}"
+            );
+        }
+        assert_eq!(
+            BitSet::from_str_radix(&format!("{:x}", empty), 16),
+            Ok(empty)
+        );
+    }
+
+    #[test]
+    fn test_bitset_parse_errors() {
+        let out_of_bounds = |index| {
+            Err(ParseBitSetError::OutOfBounds(BitIndexOutOfBounds {
+                index,
+                capacity: 10,
+            }))
+        };
+
+        assert_eq!("{1, 10}".parse::<BitSet<10, 1>>(), out_of_bounds(10));
+        assert_eq!("10000000000".parse::<BitSet<10, 1>>(), out_of_bounds(10));
+        assert_eq!("0x400".parse::<BitSet<10, 1>>(), out_of_bounds(10));
+        // leading zeros beyond the capacity are fine
+        assert_eq!("0x0000".parse::<BitSet<10, 1>>(), Ok(BitSet::new()));
+
+        // no digits at all is the empty set
+        assert_eq!("".parse::<BitSet<10, 1>>(), Ok(BitSet::new()));
+        assert_eq!("0b".parse::<BitSet<10, 1>>(), Ok(BitSet::new()));
+        assert_eq!(
+            "{1, 2".parse::<BitSet<10, 1>>(),
+            Err(ParseBitSetError::InvalidFormat)
+        );
+        assert_eq!(
+            "102".parse::<BitSet<10, 1>>(),
+            Err(ParseBitSetError::InvalidDigit('2'))
+        );
+        assert_eq!(
+            "0xfg".parse::<BitSet<10, 1>>(),
+            Err(ParseBitSetError::InvalidDigit('g'))
+        );
+        assert!(matches!(
+            "{1, x}".parse::<BitSet<10, 1>>(),
+            Err(ParseBitSetError::InvalidIndex(_))
+        ));
+    }
+
+    #[test]
+    fn test_bitset_words() {
+        let bitset: BitSet<100, 2> = [0, 64, 99].into_iter().collect();
+        assert_eq!(bitset.as_words(), &[1, 1 | 1 << 35]);
+        assert_eq!(BitSet::from_words(*bitset.as_words()), Ok(bitset));
+
+        assert_eq!(
+            BitSet::<100, 2>::from_words([0, 1 << 36]),
+            Err(BitIndexOutOfBounds {
+                index: 100,
+                capacity: 100
+            })
+        );
+    }
+
+    #[test]
+    fn test_bitset_le_bytes() {
+        let bitset: BitSet<130, 3> = [0, 9, 64, 129].into_iter().collect();
+        let bytes = bitset.to_le_bytes();
+        assert_eq!(bytes.len(), 17);
+        assert_eq!(&bytes[..2], &[1, 2]);
+        assert_eq!(bytes[8], 1);
+        assert_eq!(bytes[16], 2);
+        assert_eq!(BitSet::<130, 3>::from_le_bytes(&bytes), Ok(bitset.clone()));
+
+        // missing bytes are zero, and extra zero bytes are fine
+        assert_eq!(
+            BitSet::<130, 3>::from_le_bytes(&[1]),
+            Ok(BitSet::from_index(0))
+        );
+        let mut padded = bytes.clone();
+        padded.resize(40, 0);
+        assert_eq!(BitSet::<130, 3>::from_le_bytes(&padded), Ok(bitset));
+
+        let out_of_bounds = Err(BitIndexOutOfBounds {
+            index: 130,
+            capacity: 130,
+        });
+        let mut overflowing = bytes.clone();
+        overflowing[16] |= 4;
+        assert_eq!(BitSet::<130, 3>::from_le_bytes(&overflowing), out_of_bounds);
+        padded[30] = 1;
+        assert_eq!(
+            BitSet::<130, 3>::from_le_bytes(&padded),
+            Err(BitIndexOutOfBounds {
+                index: 240,
+                capacity: 130
+            })
+        );
+
+        assert!(BitSet::<0, 0>::new().to_le_bytes().is_empty());
+    }
+
+    #[test]
+    fn test_bitset_sparse_bytes() {
+        let bitset: BitSet<1000, { words_for_bits(1000) }> =
+            [0, 1, 5, 200, 999].into_iter().collect();
+        let bytes = bitset.to_sparse_bytes();
+        // gaps of 0, 0, 3, 194 and 798, where the last two take two bytes each
+        assert_eq!(bytes, vec![0, 0, 3, 0xC2, 0x01, 0x9E, 0x06]);
+        assert_eq!(BitSet::from_sparse_bytes(&bytes), Ok(bitset.clone()));
+
+        assert!(BitSet::<1000, 16>::new().to_sparse_bytes().is_empty());
+        assert_eq!(
+            BitSet::<1000, 16>::from_sparse_bytes(&[]),
+            Ok(BitSet::new())
+        );
+
+        assert_eq!(
+            BitSet::<1000, 16>::from_sparse_bytes(&bytes[..4]),
+            Err(DecodeBitSetError::Truncated)
+        );
+        assert_eq!(
+            BitSet::<1000, 16>::from_sparse_bytes(&[0xE8, 0x07]),
+            Err(DecodeBitSetError::OutOfBounds(BitIndexOutOfBounds {
+                index: 1000,
+                capacity: 1000
+            }))
+        );
+        assert_eq!(
+            BitSet::<1000, 16>::from_sparse_bytes(&[0xFF; 11]),
+            Err(DecodeBitSetError::InvalidVarint)
+        );
+    }
+
+    #[test]
+    fn test_bitset_relations() {
+        let a: BitSet<130, 3> = [3, 17, 64, 129].into_iter().collect();
+        let b: BitSet<130, 3> = [17, 129].into_iter().collect();
+        let c: BitSet<130, 3> = [4, 65].into_iter().collect();
+
+        assert!(b.is_subset(&a));
+        assert!(!a.is_subset(&b));
+        assert!(a.is_superset(&b));
+        assert!(!b.is_superset(&a));
+        assert!(a.is_subset(&a) && a.is_superset(&a));
+        assert!(BitSet::new().is_subset(&a));
+
+        assert!(a.is_disjoint(&c));
+        assert!(!a.is_disjoint(&b));
+        assert!(BitSet::<130, 3>::new().is_disjoint(&BitSet::new()));
+        assert_eq!(a.is_disjoint(&c), a.contains_none(&c));
+        assert_eq!(a.is_superset(&b), a.contains(&b));
+    }
+
+    #[test]
+    fn test_bitset_counts() {
+        let a: BitSet<130, 3> = [3, 17, 64, 129].into_iter().collect();
+        let b: BitSet<130, 3> = [0, 17, 65, 129].into_iter().collect();
+
+        assert_eq!(a.intersection_count(&b), 2);
+        assert_eq!(a.union_count(&b), 6);
+        assert_eq!(a.difference_count(&b), 2);
+        assert_eq!(b.difference_count(&a), 2);
+
+        for (x, y) in [(&a, &b), (&b, &a), (&a, &a), (&a, &!&a)] {
+            assert_eq!(x.intersection_count(y), x.intersection(y).count_ones());
+            assert_eq!(x.union_count(y), x.union(y).count_ones());
+            assert_eq!(x.difference_count(y), x.difference(y).count_ones());
+        }
+    }
+
+    #[test]
+    fn test_bitset_ord() {
+        let set = |indices: &[usize]| -> BitSet<130, 3> { indices.iter().copied().collect() };
+
+        assert!(set(&[]) < set(&[0]));
+        assert!(set(&[0, 1, 2]) < set(&[3]));
+        assert!(set(&[63]) < set(&[64]));
+        assert!(set(&[0, 129]) > set(&[128, 127, 64]));
+        assert!(set(&[1, 129]) > set(&[0, 129]));
+        assert_eq!(set(&[5, 70]).cmp(&set(&[5, 70])), Ordering::Equal);
+
+        let mut sets = vec![set(&[129]), set(&[]), set(&[2]), set(&[0, 1]), set(&[64])];
+        sets.sort();
+        assert_eq!(
+            sets,
+            [set(&[]), set(&[0, 1]), set(&[2]), set(&[64]), set(&[129])]
+        );
+    }
+
+    const READ: BitSet<130, 3> = BitSet::from_indices(&[0, 64]);
+    const WRITE: BitSet<130, 3> = BitSet::from_index(129);
+    const READ_WRITE: BitSet<130, 3> = READ.const_union(&WRITE);
+    const _: () = assert!(READ_WRITE.const_contains(&READ) && !READ.const_contains(&READ_WRITE));
+
+    #[test]
+    fn test_bitset_const() {
+        assert_eq!(READ, [0, 64].into_iter().collect());
+        assert_eq!(WRITE, BitSet::from_index(129));
+        assert_eq!(READ_WRITE, &READ | &WRITE);
+        assert_eq!(READ_WRITE.intersection(&WRITE), WRITE);
+        assert_eq!(READ_WRITE.const_intersection(&WRITE), WRITE);
+        assert_eq!(READ.const_intersection(&WRITE), BitSet::new());
+        assert_eq!(BitSet::<130, 3>::from_indices(&[]), BitSet::new());
+
+        static TABLE: [(BitSet<130, 3>, &str); 3] =
+            [(READ_WRITE, "read write"), (READ, "read"), (WRITE, "write")];
+        let describe = |access: &BitSet<130, 3>| {
+            TABLE
+                .iter()
+                .find(|(mask, _)| access.contains(mask))
+                .map(|(_, name)| *name)
+        };
+        assert_eq!(
+            describe(&BitSet::from_indices(&[0, 64, 129])),
+            Some("read write")
+        );
+        assert_eq!(describe(&BitSet::from_indices(&[0, 5, 64])), Some("read"));
+        assert_eq!(describe(&BitSet::from_index(0)), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "bit index is out of bounds: it must be less than the capacity")]
+    fn test_bitset_from_indices_out_of_bounds() {
+        BitSet::<130, 3>::from_indices(&[3, 130]);
+    }
+
+    #[test]
+    fn test_bitset_out_of_bounds_messages() {
+        use std::panic::catch_unwind;
+
+        let message = |result: std::thread::Result<()>| {
+            let payload = result.unwrap_err();
+            match payload.downcast_ref::<String>() {
+                Some(message) => message.clone(),
+                None => payload.downcast_ref::<&str>().unwrap().to_string(),
+            }
+        };
+
+        // the same start, with the details only known at runtime after it
+        let prefix = "bit index is out of bounds: ";
+        let set = message(catch_unwind(|| BitSet::<130, 3>::new().set(130)));
+        let from_index = message(catch_unwind(|| {
+            BitSet::<130, 3>::from_index(130);
+        }));
+        assert_eq!(
+            set,
+            "bit index is out of bounds: the index is 130 but the capacity is 130"
+        );
+        assert!(from_index.starts_with(prefix));
+    }
+
+    #[test]
+    fn test_bitset_macro() {
+        let mut bit_set: bitset!(100) = BitSet::new();
+        bit_set.set(99);
+        assert_eq!(bit_set.capacity(), 100);
+        assert_eq!(bit_set, BitSet::<100, 2>::from_index(99));
+
+        // the width is only the bits asked for, not the whole element
+        assert_eq!((!<bitset!(2)>::new()).count_ones(), 2);
+        assert_eq!(<bitset!(64 * 2)>::new().capacity(), 128);
+    }
 }
@@ -0,0 +1,155 @@
+use core::fmt;
+
+use serde::{
+    de::{self, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::BitSet;
+
+/// Prefix of the compact encoding, when followed by [BitSet::to_le_bytes]
+const DENSE: u8 = 0;
+/// Prefix of the compact encoding, when followed by [BitSet::to_sparse_bytes]
+const SPARSE: u8 = 1;
+
+/// Human readable formats get the list of set indices, e.g. `[3, 17, 64]` in JSON.
+///
+/// Other formats get a byte string, which is whichever of the dense or sparse byte encodings is
+/// smaller, prefixed with a byte to say which one it is.
+impl<const N: usize, const L: usize> Serialize for BitSet<N, L> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            return serializer.collect_seq(self.iter_indices());
+        }
+
+        let dense = self.to_le_bytes();
+        let sparse = self.to_sparse_bytes();
+        let (tag, payload) = if sparse.len() < dense.len() {
+            (SPARSE, sparse)
+        } else {
+            (DENSE, dense)
+        };
+
+        let mut bytes = Vec::with_capacity(payload.len() + 1);
+        bytes.push(tag);
+        bytes.extend_from_slice(&payload);
+
+        serializer.serialize_bytes(&bytes)
+    }
+}
+
+impl<'de, const N: usize, const L: usize> Deserialize<'de> for BitSet<N, L> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_seq(IndicesVisitor)
+        } else {
+            deserializer.deserialize_bytes(BytesVisitor)
+        }
+    }
+}
+
+struct IndicesVisitor<const N: usize, const L: usize>;
+
+impl<'de, const N: usize, const L: usize> Visitor<'de> for IndicesVisitor<N, L> {
+    type Value = BitSet<N, L>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a sequence of bit indices less than {N}")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut bit_set = BitSet::new();
+
+        while let Some(index) = seq.next_element()? {
+            bit_set.try_set(index).map_err(de::Error::custom)?;
+        }
+
+        Ok(bit_set)
+    }
+}
+
+struct BytesVisitor<const N: usize, const L: usize>;
+
+impl<'de, const N: usize, const L: usize> Visitor<'de> for BytesVisitor<N, L> {
+    type Value = BitSet<N, L>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "an encoded bitset of {N} bits")
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Self::Value, E> {
+        match bytes.split_first() {
+            Some((&DENSE, payload)) => BitSet::from_le_bytes(payload).map_err(E::custom),
+            Some((&SPARSE, payload)) => BitSet::from_sparse_bytes(payload).map_err(E::custom),
+            _ => Err(E::invalid_value(de::Unexpected::Bytes(bytes), &self)),
+        }
+    }
+
+    /// Some formats can't tell bytes apart from any other sequence
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+
+        self.visit_bytes(&bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_test::{assert_de_tokens_error, assert_tokens, Configure, Token};
+
+    use super::*;
+
+    #[test]
+    fn readable() {
+        let bitset: BitSet<130, 3> = [3, 17, 129].into_iter().collect();
+
+        assert_tokens(
+            &bitset.readable(),
+            &[
+                Token::Seq { len: None },
+                Token::U64(3),
+                Token::U64(17),
+                Token::U64(129),
+                Token::SeqEnd,
+            ],
+        );
+
+        assert_de_tokens_error::<serde_test::Readable<BitSet<130, 3>>>(
+            &[Token::Seq { len: Some(1) }, Token::U64(130)],
+            "bit index is out of bounds: the index is 130 but the capacity is 130",
+        );
+    }
+
+    #[test]
+    fn compact() {
+        // mostly empty, so the sparse encoding is smaller
+        let sparse: BitSet<130, 3> = [3, 17, 129].into_iter().collect();
+        assert_tokens(
+            &sparse.clone().compact(),
+            &[Token::Bytes(&[SPARSE, 3, 13, 111])],
+        );
+
+        // mostly full, so the dense encoding is smaller
+        let dense = !&sparse;
+        let mut bytes = vec![DENSE];
+        bytes.extend(dense.to_le_bytes());
+        // tokens can only borrow static data
+        let bytes: &'static [u8] = Box::leak(bytes.into_boxed_slice());
+        assert_tokens(&dense.compact(), &[Token::Bytes(bytes)]);
+
+        // an empty set has an empty sparse encoding
+        assert_tokens(
+            &BitSet::<130, 3>::new().compact(),
+            &[Token::Bytes(&[SPARSE])],
+        );
+
+        assert_de_tokens_error::<serde_test::Compact<BitSet<130, 3>>>(
+            &[Token::Bytes(&[2])],
+            "invalid value: byte array, expected an encoded bitset of 130 bits",
+        );
+    }
+}
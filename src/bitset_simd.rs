@@ -0,0 +1,409 @@
+//! Explicit SIMD kernels for the elements of a bitset, for when the sets are wide enough that
+//! relying on autovectorisation isn't good enough.
+//!
+//! SSE2 is part of the x86_64 baseline so it is always available, whereas AVX2 and POPCNT are
+//! detected at runtime. Any elements that don't fill a whole vector are handled by scalar code.
+
+use core::arch::x86_64::*;
+
+use crate::bitset::Element;
+
+const SSE2_LANES: usize = 2;
+const AVX2_LANES: usize = 4;
+
+macro_rules! impl_binary_kernel {
+    ( $fn:ident, $sse2_fn:ident, $avx2_fn:ident, $sse2_op:ident, $avx2_op:ident, $op:tt ) => {
+
+        /// # Panics
+        /// Panics if the slices have different lengths
+        pub(crate) fn $fn(dst: &mut [Element], src: &[Element]) {
+            assert_eq!(dst.len(), src.len());
+
+            if is_x86_feature_detected!("avx2") {
+                // SAFETY: Just checked that AVX2 is available
+                unsafe { $avx2_fn(dst, src) }
+            } else {
+                // SAFETY: SSE2 is always available on x86_64
+                unsafe { $sse2_fn(dst, src) }
+            }
+        }
+
+        /// # Safety
+        /// The caller must ensure that:
+        /// - The CPU supports SSE2.
+        /// - `src` is at least as long as `dst`.
+        #[target_feature(enable = "sse2")]
+        unsafe fn $sse2_fn(dst: &mut [Element], src: &[Element]) {
+            let vectors = dst.len() / SSE2_LANES;
+            let d = dst.as_mut_ptr().cast::<__m128i>();
+            let s = src.as_ptr().cast::<__m128i>();
+
+            for i in 0..vectors {
+                // SAFETY: `i` is within the whole vectors of both slices
+                unsafe {
+                    let v = $sse2_op(_mm_loadu_si128(d.add(i)), _mm_loadu_si128(s.add(i)));
+                    _mm_storeu_si128(d.add(i), v);
+                }
+            }
+
+            for i in vectors * SSE2_LANES..dst.len() {
+                dst[i] $op src[i];
+            }
+        }
+
+        /// # Safety
+        /// The caller must ensure that:
+        /// - The CPU supports AVX2.
+        /// - `src` is at least as long as `dst`.
+        #[target_feature(enable = "avx2")]
+        unsafe fn $avx2_fn(dst: &mut [Element], src: &[Element]) {
+            let vectors = dst.len() / AVX2_LANES;
+            let d = dst.as_mut_ptr().cast::<__m256i>();
+            let s = src.as_ptr().cast::<__m256i>();
+
+            for i in 0..vectors {
+                // SAFETY: `i` is within the whole vectors of both slices
+                unsafe {
+                    let v = $avx2_op(_mm256_loadu_si256(d.add(i)), _mm256_loadu_si256(s.add(i)));
+                    _mm256_storeu_si256(d.add(i), v);
+                }
+            }
+
+            for i in vectors * AVX2_LANES..dst.len() {
+                dst[i] $op src[i];
+            }
+        }
+
+    };
+}
+
+impl_binary_kernel!(and_assign, and_assign_sse2, and_assign_avx2, _mm_and_si128, _mm256_and_si256, &=);
+impl_binary_kernel!(or_assign, or_assign_sse2, or_assign_avx2, _mm_or_si128, _mm256_or_si256, |=);
+impl_binary_kernel!(xor_assign, xor_assign_sse2, xor_assign_avx2, _mm_xor_si128, _mm256_xor_si256, ^=);
+
+pub(crate) fn not_assign(dst: &mut [Element]) {
+    if is_x86_feature_detected!("avx2") {
+        // SAFETY: Just checked that AVX2 is available
+        unsafe { not_assign_avx2(dst) }
+    } else {
+        // SAFETY: SSE2 is always available on x86_64
+        unsafe { not_assign_sse2(dst) }
+    }
+}
+
+/// # Safety
+/// The caller must ensure that the CPU supports SSE2.
+#[target_feature(enable = "sse2")]
+unsafe fn not_assign_sse2(dst: &mut [Element]) {
+    let vectors = dst.len() / SSE2_LANES;
+    let d = dst.as_mut_ptr().cast::<__m128i>();
+    let ones = _mm_set1_epi64x(-1);
+
+    for i in 0..vectors {
+        // SAFETY: `i` is within the whole vectors of the slice
+        unsafe { _mm_storeu_si128(d.add(i), _mm_xor_si128(_mm_loadu_si128(d.add(i)), ones)) };
+    }
+
+    for bits in &mut dst[vectors * SSE2_LANES..] {
+        *bits = !*bits;
+    }
+}
+
+/// # Safety
+/// The caller must ensure that the CPU supports AVX2.
+#[target_feature(enable = "avx2")]
+unsafe fn not_assign_avx2(dst: &mut [Element]) {
+    let vectors = dst.len() / AVX2_LANES;
+    let d = dst.as_mut_ptr().cast::<__m256i>();
+    let ones = _mm256_set1_epi64x(-1);
+
+    for i in 0..vectors {
+        // SAFETY: `i` is within the whole vectors of the slice
+        unsafe {
+            _mm256_storeu_si256(
+                d.add(i),
+                _mm256_xor_si256(_mm256_loadu_si256(d.add(i)), ones),
+            )
+        };
+    }
+
+    for bits in &mut dst[vectors * AVX2_LANES..] {
+        *bits = !*bits;
+    }
+}
+
+/// Returns true if every element is zero
+pub(crate) fn is_zero(bits: &[Element]) -> bool {
+    if is_x86_feature_detected!("avx2") {
+        // SAFETY: Just checked that AVX2 is available
+        unsafe { is_zero_avx2(bits) }
+    } else {
+        // SAFETY: SSE2 is always available on x86_64
+        unsafe { is_zero_sse2(bits) }
+    }
+}
+
+/// # Safety
+/// The caller must ensure that the CPU supports SSE2.
+#[target_feature(enable = "sse2")]
+unsafe fn is_zero_sse2(bits: &[Element]) -> bool {
+    let vectors = bits.len() / SSE2_LANES;
+    let b = bits.as_ptr().cast::<__m128i>();
+    let zero = _mm_setzero_si128();
+
+    for i in 0..vectors {
+        // SAFETY: `i` is within the whole vectors of the slice
+        let v = unsafe { _mm_loadu_si128(b.add(i)) };
+        // every byte compares equal to zero
+        if _mm_movemask_epi8(_mm_cmpeq_epi8(v, zero)) != 0xFFFF {
+            return false;
+        }
+    }
+
+    bits[vectors * SSE2_LANES..].iter().all(|bits| *bits == 0)
+}
+
+/// # Safety
+/// The caller must ensure that the CPU supports AVX2.
+#[target_feature(enable = "avx2")]
+unsafe fn is_zero_avx2(bits: &[Element]) -> bool {
+    let vectors = bits.len() / AVX2_LANES;
+    let b = bits.as_ptr().cast::<__m256i>();
+
+    for i in 0..vectors {
+        // SAFETY: `i` is within the whole vectors of the slice
+        let v = unsafe { _mm256_loadu_si256(b.add(i)) };
+        if _mm256_testz_si256(v, v) == 0 {
+            return false;
+        }
+    }
+
+    bits[vectors * AVX2_LANES..].iter().all(|bits| *bits == 0)
+}
+
+/// Returns true if every bit in `sub` is also in `sup`
+///
+/// # Panics
+/// Panics if the slices have different lengths
+pub(crate) fn is_subset(sub: &[Element], sup: &[Element]) -> bool {
+    assert_eq!(sub.len(), sup.len());
+
+    if is_x86_feature_detected!("avx2") {
+        // SAFETY: Just checked that AVX2 is available
+        unsafe { is_subset_avx2(sub, sup) }
+    } else {
+        // SAFETY: SSE2 is always available on x86_64
+        unsafe { is_subset_sse2(sub, sup) }
+    }
+}
+
+/// # Safety
+/// The caller must ensure that:
+/// - The CPU supports SSE2.
+/// - `sup` is at least as long as `sub`.
+#[target_feature(enable = "sse2")]
+unsafe fn is_subset_sse2(sub: &[Element], sup: &[Element]) -> bool {
+    let vectors = sub.len() / SSE2_LANES;
+    let a = sub.as_ptr().cast::<__m128i>();
+    let b = sup.as_ptr().cast::<__m128i>();
+    let zero = _mm_setzero_si128();
+
+    for i in 0..vectors {
+        // SAFETY: `i` is within the whole vectors of both slices
+        let (a, b) = unsafe { (_mm_loadu_si128(a.add(i)), _mm_loadu_si128(b.add(i))) };
+        // bits in `sub` that are missing from `sup`
+        let missing = _mm_andnot_si128(b, a);
+        if _mm_movemask_epi8(_mm_cmpeq_epi8(missing, zero)) != 0xFFFF {
+            return false;
+        }
+    }
+
+    (vectors * SSE2_LANES..sub.len()).all(|i| sub[i] & !sup[i] == 0)
+}
+
+/// # Safety
+/// The caller must ensure that:
+/// - The CPU supports AVX2.
+/// - `sup` is at least as long as `sub`.
+#[target_feature(enable = "avx2")]
+unsafe fn is_subset_avx2(sub: &[Element], sup: &[Element]) -> bool {
+    let vectors = sub.len() / AVX2_LANES;
+    let a = sub.as_ptr().cast::<__m256i>();
+    let b = sup.as_ptr().cast::<__m256i>();
+
+    for i in 0..vectors {
+        // SAFETY: `i` is within the whole vectors of both slices
+        let (a, b) = unsafe { (_mm256_loadu_si256(a.add(i)), _mm256_loadu_si256(b.add(i))) };
+        // sets the carry flag if `!b & a` is zero, i.e. there are no bits in `sub` missing from `sup`
+        if _mm256_testc_si256(b, a) == 0 {
+            return false;
+        }
+    }
+
+    (vectors * AVX2_LANES..sub.len()).all(|i| sub[i] & !sup[i] == 0)
+}
+
+pub(crate) fn count_ones(bits: &[Element]) -> usize {
+    if is_x86_feature_detected!("avx2") {
+        // SAFETY: Just checked that AVX2 is available
+        unsafe { count_ones_avx2(bits) }
+    } else if is_x86_feature_detected!("popcnt") {
+        // SAFETY: Just checked that POPCNT is available
+        unsafe { count_ones_popcnt(bits) }
+    } else {
+        bits.iter().map(|bits| bits.count_ones() as usize).sum()
+    }
+}
+
+/// # Safety
+/// The caller must ensure that the CPU supports POPCNT.
+#[target_feature(enable = "popcnt")]
+unsafe fn count_ones_popcnt(bits: &[Element]) -> usize {
+    bits.iter().map(|bits| bits.count_ones() as usize).sum()
+}
+
+/// Counts bits a nibble at a time using a shuffle as a lookup table, then sums the bytes of each
+/// lane, as AVX2 has no vector popcount of its own.
+///
+/// # Safety
+/// The caller must ensure that the CPU supports AVX2.
+#[target_feature(enable = "avx2")]
+unsafe fn count_ones_avx2(bits: &[Element]) -> usize {
+    let vectors = bits.len() / AVX2_LANES;
+    let b = bits.as_ptr().cast::<__m256i>();
+
+    #[rustfmt::skip]
+    let lookup = _mm256_setr_epi8(
+        0, 1, 1, 2, 1, 2, 2, 3, 1, 2, 2, 3, 2, 3, 3, 4,
+        0, 1, 1, 2, 1, 2, 2, 3, 1, 2, 2, 3, 2, 3, 3, 4,
+    );
+    let low_mask = _mm256_set1_epi8(0x0f);
+    let zero = _mm256_setzero_si256();
+    let mut total = zero;
+
+    for i in 0..vectors {
+        // SAFETY: `i` is within the whole vectors of the slice
+        let v = unsafe { _mm256_loadu_si256(b.add(i)) };
+        let lo = _mm256_and_si256(v, low_mask);
+        let hi = _mm256_and_si256(_mm256_srli_epi16(v, 4), low_mask);
+        let counts = _mm256_add_epi8(
+            _mm256_shuffle_epi8(lookup, lo),
+            _mm256_shuffle_epi8(lookup, hi),
+        );
+        // each byte is at most 8, so summing them into 64-bit lanes can't overflow
+        total = _mm256_add_epi64(total, _mm256_sad_epu8(counts, zero));
+    }
+
+    let mut lanes = [0u64; AVX2_LANES];
+    // SAFETY: `lanes` is exactly the size of one vector
+    unsafe { _mm256_storeu_si256(lanes.as_mut_ptr().cast::<__m256i>(), total) };
+
+    let tail: usize = bits[vectors * AVX2_LANES..]
+        .iter()
+        .map(|bits| bits.count_ones() as usize)
+        .sum();
+
+    lanes.iter().sum::<u64>() as usize + tail
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    pub struct XORShift64 {
+        state: u64,
+    }
+
+    impl XORShift64 {
+        pub fn new(seed: u64) -> Self {
+            Self { state: seed }
+        }
+
+        pub fn rand(&mut self) -> u64 {
+            let mut x = self.state;
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            self.state = x;
+            x
+        }
+    }
+
+    /// Random elements of every length up to a few vectors, plus some sparse and dense patterns
+    fn samples() -> Vec<(Vec<Element>, Vec<Element>)> {
+        let mut rng = XORShift64::new(0x9E37_79B9_7F4A_7C15);
+        let mut samples = Vec::new();
+
+        for len in 0..=19 {
+            let a: Vec<Element> = (0..len).map(|_| rng.rand()).collect();
+            let b: Vec<Element> = (0..len).map(|_| rng.rand()).collect();
+            let sparse: Vec<Element> = (0..len).map(|i| (i == len - 1) as Element).collect();
+
+            samples.push((a.clone(), b.clone()));
+            samples.push((a.clone(), vec![0; len]));
+            samples.push((vec![Element::MAX; len], a.clone()));
+            samples.push((sparse.clone(), a.iter().map(|a| a | 1).collect()));
+            samples.push((vec![0; len], sparse));
+            // `a` is a subset of `a | b`
+            samples.push((a.clone(), a.iter().zip(&b).map(|(a, b)| a | b).collect()));
+        }
+
+        samples
+    }
+
+    #[test]
+    fn binary_kernels_match_scalar() {
+        for (a, b) in samples() {
+            let mut and = a.clone();
+            and_assign(&mut and, &b);
+            let mut or = a.clone();
+            or_assign(&mut or, &b);
+            let mut xor = a.clone();
+            xor_assign(&mut xor, &b);
+
+            for i in 0..a.len() {
+                assert_eq!(and[i], a[i] & b[i]);
+                assert_eq!(or[i], a[i] | b[i]);
+                assert_eq!(xor[i], a[i] ^ b[i]);
+            }
+
+            // the SSE2 kernels are always available, but are only used if AVX2 isn't
+            let mut and = a.clone();
+            unsafe { and_assign_sse2(&mut and, &b) };
+            let mut or = a.clone();
+            unsafe { or_assign_sse2(&mut or, &b) };
+            let mut xor = a.clone();
+            unsafe { xor_assign_sse2(&mut xor, &b) };
+
+            for i in 0..a.len() {
+                assert_eq!(and[i], a[i] & b[i]);
+                assert_eq!(or[i], a[i] | b[i]);
+                assert_eq!(xor[i], a[i] ^ b[i]);
+            }
+        }
+    }
+
+    #[test]
+    fn unary_kernels_match_scalar() {
+        for (a, b) in samples() {
+            let mut not = a.clone();
+            not_assign(&mut not);
+            assert!(not.iter().zip(&a).all(|(not, a)| *not == !a));
+
+            let mut not = a.clone();
+            unsafe { not_assign_sse2(&mut not) };
+            assert!(not.iter().zip(&a).all(|(not, a)| *not == !a));
+
+            let scalar_is_zero = a.iter().all(|bits| *bits == 0);
+            assert_eq!(is_zero(&a), scalar_is_zero);
+            assert_eq!(unsafe { is_zero_sse2(&a) }, scalar_is_zero);
+
+            let scalar_is_subset = a.iter().zip(&b).all(|(a, b)| a & !b == 0);
+            assert_eq!(is_subset(&a, &b), scalar_is_subset);
+            assert_eq!(unsafe { is_subset_sse2(&a, &b) }, scalar_is_subset);
+
+            let scalar_count: usize = a.iter().map(|bits| bits.count_ones() as usize).sum();
+            assert_eq!(count_ones(&a), scalar_count);
+        }
+    }
+}
@@ -1,6 +1,19 @@
-use core::{alloc::Layout, any::TypeId, cell::UnsafeCell, marker::PhantomData, ptr::NonNull};
+use core::{
+    alloc::Layout,
+    any::{type_name, TypeId},
+    cell::UnsafeCell,
+    fmt,
+    iter::FusedIterator,
+    marker::PhantomData,
+    mem::{ManuallyDrop, MaybeUninit},
+    ops::{Deref, DerefMut},
+    ptr::NonNull,
+    slice::{Iter, IterMut},
+};
 use std::alloc;
 
+use crate::{Allocator, Global};
+
 const DEFAULT_CAPACITY: usize = 8;
 
 /// This is a wrapper around a [NonNull] pointer, for the sake of associating a lifetime, as well as
@@ -103,6 +116,17 @@ pub struct ErasedType {
     type_id: TypeId,
     layout: Layout,
     drop: unsafe fn(Ptr),
+    clone: CloneKind,
+}
+
+/// How the values of an [ErasedType] can be duplicated
+#[derive(Clone, Copy)]
+enum CloneKind {
+    None,
+    /// The value can be duplicated bitwise
+    Copy,
+    /// Clones the value at the first pointer into the uninitialised memory at the second
+    Clone(unsafe fn(Ptr, Ptr)),
 }
 
 impl ErasedType {
@@ -111,17 +135,81 @@ impl ErasedType {
             type_id: TypeId::of::<T>(),
             layout: Layout::new::<T>(),
             drop: |ptr| unsafe { ptr.drop_as::<T>() },
+            clone: CloneKind::None,
+        }
+    }
+
+    /// Creates an erased type whose values can be cloned, e.g. by [ErasedVec::try_clone]
+    pub fn new_cloneable<T: Clone + 'static>() -> Self {
+        Self {
+            clone: CloneKind::Clone(|src, dst| unsafe {
+                dst.as_ptr().cast::<T>().write(src.as_ref::<T>().clone())
+            }),
+            ..Self::new::<T>()
         }
     }
 
-    pub fn from_raw_parts(type_id: TypeId, layout: Layout, drop: unsafe fn(Ptr)) -> Self {
+    /// Creates an erased type whose values are cloned by copying their bytes, which is faster than
+    /// [ErasedType::new_cloneable] for many values at once
+    pub fn new_copy<T: Copy + 'static>() -> Self {
+        Self {
+            clone: CloneKind::Copy,
+            ..Self::new::<T>()
+        }
+    }
+
+    /// Creates an erased type from its parts, e.g. for a type that is only known at runtime
+    ///
+    /// # Safety
+    /// The caller must ensure that:
+    /// - The type id, layout and drop function all describe the same type.
+    /// - The drop function can be called on any value of the type.
+    ///
+    /// # Panics
+    /// Panics if the size of the layout is not a multiple of its alignment, as it is also the stride
+    /// between elements
+    pub unsafe fn from_raw_parts(type_id: TypeId, layout: Layout, drop: unsafe fn(Ptr)) -> Self {
+        assert!(
+            layout == layout.pad_to_align(),
+            "The size of the layout must be a multiple of its alignment"
+        );
+
         Self {
             type_id,
             layout,
             drop,
+            clone: CloneKind::None,
+        }
+    }
+
+    /// The same as [ErasedType::from_raw_parts], along with a function that clones the value at the
+    /// first pointer into the uninitialised memory at the second, e.g. for [ErasedVec::try_clone]
+    ///
+    /// # Safety
+    /// The same as [ErasedType::from_raw_parts], and the clone function must also describe the
+    /// same type
+    ///
+    /// # Panics
+    /// The same as [ErasedType::from_raw_parts]
+    pub unsafe fn from_raw_parts_cloneable(
+        type_id: TypeId,
+        layout: Layout,
+        drop: unsafe fn(Ptr),
+        clone: unsafe fn(Ptr, Ptr),
+    ) -> Self {
+        Self {
+            clone: CloneKind::Clone(clone),
+            // SAFETY: Upheld by the caller
+            ..unsafe { Self::from_raw_parts(type_id, layout, drop) }
         }
     }
 
+    /// Returns true if values of this type can be cloned, e.g. by [ErasedVec::try_clone]
+    #[inline]
+    pub fn is_cloneable(&self) -> bool {
+        !matches!(self.clone, CloneKind::None)
+    }
+
     /// # Safety
     /// The caller must ensure that:
     /// - Any aliases of this pointer are not used after calling this function.
@@ -129,12 +217,89 @@ impl ErasedType {
     pub unsafe fn dispose(&self, ptr: Ptr) {
         (self.drop)(ptr);
     }
+
+    #[inline]
+    pub fn type_id(&self) -> TypeId {
+        self.type_id
+    }
+
+    /// The layout of a single element, whose size is always a multiple of its alignment, so it is
+    /// also the stride between elements
+    #[inline]
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    /// Returns true if this is the erased form of `T`. The layout is compared as well as the type
+    /// id, as a mistaken [ErasedType::from_raw_parts] could pair any type id with any layout.
+    #[inline]
+    pub fn is<T: 'static>(&self) -> bool {
+        self.type_id == TypeId::of::<T>() && self.layout == Layout::new::<T>()
+    }
+
+    /// Returns true if values of the two types can be used interchangeably
+    #[inline]
+    fn same_as(&self, other: &ErasedType) -> bool {
+        self.type_id == other.type_id && self.layout == other.layout
+    }
+
+    #[inline]
+    fn check<T: 'static>(&self) -> Result<(), TypeMismatch> {
+        if self.is::<T>() {
+            Ok(())
+        } else {
+            Err(TypeMismatch {
+                expected: self.type_id,
+                found: TypeId::of::<T>(),
+                found_name: type_name::<T>(),
+            })
+        }
+    }
+}
+
+/// Error returned when a typed method is called on an [ErasedVec] with a type other than the one it
+/// was created with
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TypeMismatch {
+    pub expected: TypeId,
+    pub found: TypeId,
+    pub found_name: &'static str,
+}
+
+impl fmt::Display for TypeMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "type mismatch: the vec does not hold elements of type `{}`",
+            self.found_name
+        )
+    }
+}
+
+impl std::error::Error for TypeMismatch {}
+
+/// Error returned when cloning an [ErasedVec] whose [ErasedType] was not created with a way to
+/// clone its values
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NotCloneable {
+    pub type_id: TypeId,
+}
+
+impl fmt::Display for NotCloneable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the erased type of the vec is not cloneable")
+    }
 }
 
+impl std::error::Error for NotCloneable {}
+
 /// A type-erased vector that can store any type.
 ///
+/// Memory comes from the global allocator by default, or any other [Allocator] given to one of
+/// the `_in` constructors.
+///
 /// Almost every method on this type is unsafe
-pub struct ErasedVec {
+pub struct ErasedVec<A: Allocator = Global> {
     item: ErasedType,
     layout: Layout,
     head: NonNull<u8>,
@@ -142,38 +307,68 @@ pub struct ErasedVec {
     len: usize,
     /// The number of elements that can be stored in the vec
     capacity: usize,
+    allocator: A,
 }
 
 impl ErasedVec {
     #[inline]
     pub fn new<T: 'static>() -> Self {
-        Self::with_capacity::<T>(DEFAULT_CAPACITY)
+        Self::new_in::<T>(Global)
     }
 
     #[inline]
     pub fn with_capacity<T: 'static>(capacity: usize) -> Self {
-        let item = ErasedType::new::<T>();
-
-        Self::with_capacity_erased_type(item, capacity)
+        Self::with_capacity_in::<T>(capacity, Global)
     }
 
     #[inline]
     pub fn from_erased_type(item: ErasedType) -> Self {
-        Self::with_capacity_erased_type(item, DEFAULT_CAPACITY)
+        Self::from_erased_type_in(item, Global)
     }
 
+    /// Zero-sized types never allocate, and have a capacity of `usize::MAX`. Neither does a
+    /// capacity of zero, which allocates when the first element is pushed.
     #[inline]
     pub fn with_capacity_erased_type(item: ErasedType, capacity: usize) -> Self {
-        // TODO: ZST support
-        if capacity == 0 {
-            panic!("Capacity must be greater than 0");
-        }
+        Self::with_capacity_erased_type_in(item, capacity, Global)
+    }
+}
+
+impl<A: Allocator> ErasedVec<A> {
+    #[inline]
+    pub fn new_in<T: 'static>(allocator: A) -> Self {
+        Self::with_capacity_in::<T>(DEFAULT_CAPACITY, allocator)
+    }
+
+    #[inline]
+    pub fn with_capacity_in<T: 'static>(capacity: usize, allocator: A) -> Self {
+        let item = ErasedType::new::<T>();
+
+        Self::with_capacity_erased_type_in(item, capacity, allocator)
+    }
+
+    #[inline]
+    pub fn from_erased_type_in(item: ErasedType, allocator: A) -> Self {
+        Self::with_capacity_erased_type_in(item, DEFAULT_CAPACITY, allocator)
+    }
 
-        let layout =
-            Layout::from_size_align(item.layout.size() * capacity, item.layout.align()).unwrap();
+    /// See [ErasedVec::with_capacity_erased_type]
+    #[inline]
+    pub fn with_capacity_erased_type_in(item: ErasedType, capacity: usize, allocator: A) -> Self {
+        let (layout, capacity) = if item.layout.size() == 0 {
+            (item.layout, usize::MAX)
+        } else {
+            let layout = array_layout(item.layout, capacity).expect("Capacity overflow");
+            (layout, capacity)
+        };
 
-        let head = NonNull::new(unsafe { alloc::alloc(layout) })
-            .unwrap_or_else(|| alloc::handle_alloc_error(layout));
+        let head = if layout.size() == 0 {
+            dangling(layout)
+        } else {
+            // SAFETY: The layout has a non-zero size
+            unsafe { allocator.allocate(layout) }
+                .unwrap_or_else(|| alloc::handle_alloc_error(layout))
+        };
 
         Self {
             item,
@@ -181,9 +376,15 @@ impl ErasedVec {
             head,
             len: 0,
             capacity,
+            allocator,
         }
     }
 
+    #[inline]
+    pub fn allocator(&self) -> &A {
+        &self.allocator
+    }
+
     #[inline]
     pub fn len(&self) -> usize {
         self.len
@@ -211,7 +412,6 @@ impl ErasedVec {
     /// - The pointer should not, for some reason, represent the end (len-wise) of this vec.
     #[inline]
     pub unsafe fn push(&mut self, value: Ptr) {
-        // TODO: ZST support
         self.reserve(1);
 
         // SAFETY:
@@ -230,27 +430,72 @@ impl ErasedVec {
         self.len += 1;
     }
 
+    /// Moves `count` contiguous values into the end of the vec
+    ///
     /// # Safety
     /// The caller must ensure that:
     /// - The pointer is aligned to the type that this vec was created with.
-    /// - The pointer is actually valid for reading a value of the type.
-    /// - The pointer should not, for some reason, represent the end (len-wise) of this vec.
+    /// - The pointer is actually valid for reading `count` contiguous values of the type.
+    /// - The pointer does not point into this vec, as it may be reallocated.
+    /// - The values are not used or dropped after this, as the vec now owns them.
     #[inline]
     pub unsafe fn push_many(&mut self, values: Ptr, count: usize) {
-        // TODO: ZST support
         self.reserve(count);
 
+        // SAFETY:
+        // - Dst pointer is aligned and has room for `count` elements, as we just reserved them.
+        // - The src pointer is valid for `count` elements, and doesn't overlap the vec, as is
+        //   deferred to the caller.
         unsafe {
             core::ptr::copy_nonoverlapping(
                 values.as_ptr(),
                 self.get_unchecked(self.len).as_ptr(),
-                self.item.layout.size(),
+                self.item.layout.size() * count,
             )
         }
 
         self.len += count;
     }
 
+    /// Copies every element of `other` onto the end of this vec
+    ///
+    /// # Panics
+    /// Panics if `other` was not created with the same type as this vec
+    ///
+    /// # Safety
+    /// The caller must ensure that:
+    /// - The type can be duplicated bitwise, i.e. it is [Copy], or the elements in `other` are not
+    ///   dropped, as both vecs would otherwise drop the same values.
+    #[inline]
+    pub unsafe fn extend_from_erased_slice<B: Allocator>(&mut self, other: &ErasedVec<B>) {
+        assert!(
+            self.item.same_as(&other.item),
+            "Cannot extend a vec from a vec of a different type"
+        );
+
+        // SAFETY: `other` holds `other.len` valid elements of the same type, and can't alias this
+        // vec as we hold a mutable reference to it.
+        unsafe { self.push_many(Ptr::new(other.head), other.len) }
+    }
+
+    /// Copies every value in `values` onto the end of the vec
+    ///
+    /// # Panics
+    /// Panics if `T` is not the type that this vec was created with
+    #[inline]
+    pub fn extend_from_slice<T: Copy + 'static>(&mut self, values: &[T]) {
+        assert!(
+            self.item.is::<T>(),
+            "Cannot extend a vec from a slice of a different type"
+        );
+
+        // SAFETY:
+        // - `T` is the item type, so the values are aligned and valid, and are [Copy] so they can
+        //   still be used by the caller afterwards.
+        // - The slice can't alias this vec as we hold a mutable reference to it.
+        unsafe { self.push_many(Ptr::from(values.as_ptr().cast_mut()), values.len()) }
+    }
+
     /// # Safety
     /// The caller must ensure that:
     /// - The data associated with this pointer is **not** dropped, as the vec will continue to hold a reference
@@ -353,7 +598,8 @@ impl ErasedVec {
         // SAFETY: Bounds check deferred to the caller.
         let middle = unsafe { self.get_unchecked(index) };
 
-        debug_assert_ne!(end.as_ptr(), middle.as_ptr());
+        // zero-sized elements all share the same address, and swapping them is a no-op
+        debug_assert!(self.item.layout.size() == 0 || end.as_ptr() != middle.as_ptr());
 
         // SAFETY:
         // - `middle` and `end` pointers are different and, as they vary by increments of one element's size,
@@ -369,6 +615,9 @@ impl ErasedVec {
         end
     }
 
+    /// The element is removed from the vec before it is dropped, so the vec is still valid if its
+    /// destructor panics.
+    ///
     /// # Safety
     /// The caller must ensure that:
     /// - Any existing pointers to the data are not used after this.
@@ -398,19 +647,191 @@ impl ErasedVec {
         }
     }
 
+    /// Moves the value into the vec at `index`, shifting every element after it up by one
+    ///
+    /// # Panics
+    /// Panics if `index` is greater than the length of the vec
+    ///
+    /// # Safety
+    /// The caller must ensure that:
+    /// - The pointer is aligned to the type that this vec was created with.
+    /// - The pointer is actually valid for reading a value of the type.
+    /// - The pointer does not point into this vec, as it may be reallocated.
+    #[inline]
+    pub unsafe fn insert(&mut self, index: usize, value: Ptr) {
+        assert!(
+            index <= self.len,
+            "Insertion index (is {index}) should be <= len (is {})",
+            self.len
+        );
+
+        self.reserve(1);
+
+        // SAFETY:
+        // - We just reserved room for one more element, so the shifted elements stay in bounds.
+        // - `copy` handles the overlap between the old and new positions of the tail.
+        // - Validity of the src pointer is deferred to the caller.
+        unsafe {
+            let dst = self.get_unchecked(index).as_ptr();
+            core::ptr::copy(
+                dst,
+                dst.add(self.item.layout.size()),
+                (self.len - index) * self.item.layout.size(),
+            );
+            core::ptr::copy_nonoverlapping(value.as_ptr(), dst, self.item.layout.size());
+        }
+
+        self.len += 1;
+    }
+
+    /// Removes the element at `index`, shifting every element after it down by one. This is O(n),
+    /// so prefer [ErasedVec::swap_remove] when order doesn't matter.
+    ///
+    /// The returned pointer is to the end of the vec, where the removed element is moved to, so it
+    /// is only valid until the vec is next modified.
+    ///
+    /// # Safety
+    /// The caller must ensure that:
+    /// - The data associated with this pointer **is** dropped appropriately if necessary, by calling `dispose`
+    ///   on the vec. Not doing so will potentially leak memory, as the vec will no longer track this item.
+    #[inline]
+    pub unsafe fn remove(&mut self, index: usize) -> Option<Ptr<'_>> {
+        if index >= self.len {
+            return None;
+        }
+
+        // SAFETY: `index` is within bounds, so the tail from `index` to `len` is allocated. It is
+        // viewed as `MaybeUninit` bytes, as any padding within the elements may be uninitialised.
+        let tail = unsafe {
+            core::slice::from_raw_parts_mut(
+                self.get_unchecked(index).as_ptr().cast::<MaybeUninit<u8>>(),
+                (self.len - index) * self.item.layout.size(),
+            )
+        };
+        // move the removed element to the end, as there is nowhere else to put it
+        tail.rotate_left(self.item.layout.size());
+
+        // SAFETY: Just moved the removed element to the end of the vec
+        Some(unsafe { self.pop_unchecked() })
+    }
+
     /// # Safety
     /// The caller must ensure that:
     /// - Any existing pointers to the data are not used after this.
     #[inline]
-    pub unsafe fn clear(&mut self) {
-        for i in 0..self.len {
-            // SAFETY: `self.len` is within bounds
-            let ptr = unsafe { self.get_unchecked(i) };
-            // SAFETY: `ptr` is not used after this call
+    pub unsafe fn remove_drop(&mut self, index: usize) {
+        // SAFETY: dropped immediately
+        if let Some(ptr) = unsafe { self.remove(index).map(|ptr| ptr.extend_lifetime()) } {
+            // SAFETY: `item` is not used after this call
             unsafe { self.item.dispose(ptr) }
         }
+    }
 
-        self.len = 0;
+    /// Drops every element from `len` onwards. Does nothing if the vec is already shorter.
+    ///
+    /// If a destructor panics, the rest of the elements are still dropped, and the vec is left
+    /// with a length of `len`.
+    ///
+    /// # Safety
+    /// The caller must ensure that:
+    /// - Any existing pointers to the dropped data are not used after this.
+    #[inline]
+    pub unsafe fn truncate(&mut self, len: usize) {
+        if len >= self.len {
+            return;
+        }
+
+        let end = self.len;
+        // forget the tail first, so that it can't be dropped again if a destructor panics
+        self.len = len;
+
+        // SAFETY: The elements from `len` to `end` are initialised, and no longer tracked by the vec
+        unsafe { DropGuard::new(&self.item, self.head, len, end).drop_remaining() }
+    }
+
+    /// Swaps the elements at indices `a` and `b`
+    ///
+    /// # Panics
+    /// Panics if either index is out of bounds
+    #[inline]
+    pub fn swap(&mut self, a: usize, b: usize) {
+        assert!(
+            a < self.len && b < self.len,
+            "Swap indices (are {a} and {b}) should be < len (is {})",
+            self.len
+        );
+
+        if a != b {
+            // SAFETY: Both indices are within bounds, and are different elements, so don't overlap
+            unsafe {
+                core::ptr::swap_nonoverlapping(
+                    self.get_unchecked(a).as_ptr(),
+                    self.get_unchecked(b).as_ptr(),
+                    self.item.layout.size(),
+                )
+            }
+        }
+    }
+
+    /// Moves every element from `at` onwards into a new vec of the same type
+    ///
+    /// # Panics
+    /// Panics if `at` is greater than the length of the vec
+    #[inline]
+    pub fn split_off(&mut self, at: usize) -> ErasedVec<A>
+    where
+        A: Clone,
+    {
+        assert!(
+            at <= self.len,
+            "Split index (is {at}) should be <= len (is {})",
+            self.len
+        );
+
+        let count = self.len - at;
+        let mut other = ErasedVec::with_capacity_erased_type_in(
+            self.item.clone(),
+            count,
+            self.allocator.clone(),
+        );
+        self.len = at;
+
+        // SAFETY: The tail is still initialised, but is now owned by `other` alone
+        unsafe { other.push_many(self.get_unchecked(at), count) };
+        other
+    }
+
+    /// Moves every element of `other` onto the end of this vec, leaving `other` empty
+    ///
+    /// # Panics
+    /// Panics if `other` was not created with the same type as this vec
+    #[inline]
+    pub fn append<B: Allocator>(&mut self, other: &mut ErasedVec<B>) {
+        assert!(
+            self.item.same_as(&other.item),
+            "Cannot append a vec of a different type"
+        );
+
+        let count = other.len;
+        other.len = 0;
+
+        // SAFETY: The elements of `other` are still initialised, but are now owned by this vec
+        // alone, and can't alias this vec as we hold a mutable reference to it.
+        unsafe { self.push_many(Ptr::new(other.head), count) };
+    }
+
+    /// Drops every element.
+    ///
+    /// If a destructor panics, the rest of the elements are still dropped, and the vec is left
+    /// empty, so no element is ever dropped twice. A second panic while unwinding aborts.
+    ///
+    /// # Safety
+    /// The caller must ensure that:
+    /// - Any existing pointers to the data are not used after this.
+    #[inline]
+    pub unsafe fn clear(&mut self) {
+        // SAFETY: `truncate` upholds the same requirements
+        unsafe { self.truncate(0) }
     }
 
     /// # Safety
@@ -437,136 +858,644 @@ impl ErasedVec {
         unsafe { core::slice::from_raw_parts(self.head.as_ptr().cast::<UnsafeCell<T>>(), self.len) }
     }
 
+    // Type-checked versions of the methods above, which compare `T` against the type that the vec
+    // was created with.
+
     #[inline]
-    unsafe fn reserve(&mut self, additional: usize) {
-        let required = self.len + additional;
-        if required > self.capacity {
-            self.grow(required.next_power_of_two());
-        }
-    }
+    pub fn push_typed<T: 'static>(&mut self, value: T) -> Result<(), TypeMismatch> {
+        self.item.check::<T>()?;
 
-    /// # Safety
-    /// The caller must ensure that:
-    /// - The item size and capacity are greater than zero (ZST)
-    /// - The new capacity is greater than the current capacity.
-    unsafe fn grow(&mut self, new_capacity: usize) {
-        self.capacity = new_capacity;
+        let mut value = ManuallyDrop::new(value);
+        // SAFETY: `T` is the item type, and the vec takes ownership of the value
+        unsafe { self.push(Ptr::from(&mut *value)) };
+        Ok(())
+    }
 
-        let new_layout = Layout::from_size_align(
-            self.item.layout.size() * self.capacity,
-            self.item.layout.align(),
-        )
-        .expect("Invalid layout");
+    #[inline]
+    pub fn get_typed<T: 'static>(&self, index: usize) -> Result<Option<&T>, TypeMismatch> {
+        Ok(self.as_slice_checked::<T>()?.get(index))
+    }
 
-        // SAFETY:
-        // - self.data` is guaranteed to be non-null.
-        // -`self.data_layout` is valid, otherwise we will have already panicked.
-        let new_head =
-            unsafe { alloc::realloc(self.head.as_ptr(), self.layout, new_layout.size()) };
+    #[inline]
+    pub fn get_typed_mut<T: 'static>(
+        &mut self,
+        index: usize,
+    ) -> Result<Option<&mut T>, TypeMismatch> {
+        Ok(self.as_slice_mut_checked::<T>()?.get_mut(index))
+    }
 
-        self.layout = new_layout;
+    #[inline]
+    pub fn pop_typed<T: 'static>(&mut self) -> Result<Option<T>, TypeMismatch> {
+        self.item.check::<T>()?;
 
-        self.head =
-            NonNull::new(new_head).unwrap_or_else(|| alloc::handle_alloc_error(self.layout));
+        // SAFETY: `T` is the item type, and the value is read out of the vec, which no longer
+        // tracks it, so it won't be dropped twice.
+        Ok(unsafe { self.pop() }.map(|ptr| unsafe { ptr.as_ptr().cast::<T>().read() }))
     }
-}
 
-impl Drop for ErasedVec {
-    fn drop(&mut self) {
-        unsafe { self.clear() };
+    #[inline]
+    pub fn as_slice_checked<T: 'static>(&self) -> Result<&[T], TypeMismatch> {
+        self.item.check::<T>()?;
+        // SAFETY: `T` is the item type
+        Ok(unsafe { self.as_slice() })
+    }
 
-        // TODO: ZST support (no need to deallocate if size is zero)
-        unsafe {
-            alloc::dealloc(self.head.as_ptr(), self.layout);
-        }
+    #[inline]
+    pub fn as_slice_mut_checked<T: 'static>(&mut self) -> Result<&mut [T], TypeMismatch> {
+        self.item.check::<T>()?;
+        // SAFETY: `T` is the item type
+        Ok(unsafe { self.as_slice_mut() })
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use core::mem::ManuallyDrop;
+    /// Returns a view of the elements as `&[T]`, or `None` if `T` is not the item type, as checked
+    /// by [ErasedType::is]
+    #[inline]
+    pub fn view<T: 'static>(&self) -> Option<TypedView<'_, T>> {
+        let slice = self.as_slice_checked().ok()?;
+        Some(TypedView { slice })
+    }
 
-    use super::*;
+    /// Returns a view of the elements as `&mut [T]`, or `None` if `T` is not the item type, as
+    /// checked by [ErasedType::is]
+    #[inline]
+    pub fn view_mut<T: 'static>(&mut self) -> Option<TypedViewMut<'_, T>> {
+        let slice = self.as_slice_mut_checked().ok()?;
+        Some(TypedViewMut { slice })
+    }
 
-    #[test]
-    fn drop_test() {
-        unsafe { _drop_test() }
+    /// Iterates over a pointer to each element, for code that only knows the [ErasedType]
+    #[inline]
+    pub fn iter_ptrs(&self) -> PtrIter<'_> {
+        PtrIter {
+            head: self.head,
+            stride: self.item.layout.size(),
+            front: 0,
+            back: self.len,
+            _marker: PhantomData,
+        }
     }
 
-    unsafe fn _drop_test() {
-        let mut vec = ErasedVec::new::<String>();
-        let mut element = ManuallyDrop::new(String::from("Hello World"));
-        vec.push(Ptr::from(&mut element));
+    /// Iterates over the elements in chunks of `chunk_len`, yielding a pointer to the first
+    /// element of each chunk along with the number of elements in it. Only the last chunk can be
+    /// shorter. With a known type, `view::<T>()` and [slice::chunks_exact] do the same.
+    ///
+    /// # Panics
+    /// Panics if `chunk_len` is zero
+    #[inline]
+    pub fn iter_chunk_ptrs(&self, chunk_len: usize) -> ChunkPtrIter<'_> {
+        assert!(chunk_len != 0, "Chunk length must be greater than 0");
 
-        assert_eq!(vec.len(), 1);
-        assert_eq!(vec.capacity(), 8);
+        ChunkPtrIter {
+            ptrs: self.iter_ptrs(),
+            chunk_len,
+        }
+    }
 
-        // 'removed' from vec, but still lives there so we are responsible for dropping it.
-        // ... also, this mess of method chaining is to coerce the associated lifetime so I can continue
-        // working on the vec for testing purposes.
-        let element = vec
-            .pop()
-            .unwrap()
-            .as_ptr()
-            .cast::<String>()
-            .as_mut()
-            .unwrap();
-        println!("1: {}", element);
-        assert_eq!(vec.len(), 0);
-        assert_eq!(vec.capacity(), 8);
+    /// Clones every element into a new vec, with a capacity of the length of this one
+    #[inline]
+    pub fn try_clone(&self) -> Result<ErasedVec<A>, NotCloneable>
+    where
+        A: Clone,
+    {
+        if !self.item.is_cloneable() {
+            return Err(NotCloneable {
+                type_id: self.item.type_id,
+            });
+        }
 
-        // element still not dropped, because vec doesn't know about it
-        vec.clear();
-        println!("2: {}", element);
+        let mut clone = ErasedVec::with_capacity_erased_type_in(
+            self.item.clone(),
+            self.len,
+            self.allocator.clone(),
+        );
+
+        match self.item.clone {
+            // already returned above
+            CloneKind::None => {}
+            // SAFETY: The type is [Copy], so the elements can be duplicated bitwise
+            CloneKind::Copy => unsafe { clone.push_many(Ptr::new(self.head), self.len) },
+            CloneKind::Clone(clone_fn) => {
+                for i in 0..self.len {
+                    // SAFETY:
+                    // - `i` is within the bounds of this vec, and the capacity of the clone.
+                    // - The length is only increased once each element is written, so if a clone
+                    //   panics, only the elements cloned so far are dropped.
+                    unsafe { clone_fn(self.get_unchecked(i), clone.get_unchecked(i)) };
+                    clone.len += 1;
+                }
+            }
+        }
 
-        // drop the element using vec
-        vec.item.dispose(Ptr::from(element));
+        Ok(clone)
     }
 
-    #[test]
-    fn push_pop_test() {
-        unsafe { _push_pop_test() }
+    /// Reserves room for at least `additional` more elements, rounding the capacity up to the
+    /// next power of two so that repeated pushes are amortised
+    ///
+    /// # Panics
+    /// Panics if the required capacity overflows `usize`, or the size of the allocation overflows
+    /// `isize`. Aborts if the allocation fails.
+    #[inline]
+    pub fn reserve(&mut self, additional: usize) {
+        handle_reserve(self.try_reserve(additional));
     }
 
-    unsafe fn _push_pop_test() {
-        let mut vec = ErasedVec::new::<i32>();
-
-        assert_eq!(vec.len(), 0);
-        assert_eq!(vec.capacity(), 8);
+    /// Reserves room for exactly `additional` more elements, if there isn't already room
+    ///
+    /// # Panics
+    /// Panics if the required capacity overflows `usize`, or the size of the allocation overflows
+    /// `isize`. Aborts if the allocation fails.
+    #[inline]
+    pub fn reserve_exact(&mut self, additional: usize) {
+        handle_reserve(self.try_reserve_exact(additional));
+    }
 
-        for i in 0..10 {
-            vec.push(Ptr::from(&i as *const _ as *mut u8));
+    /// The same as [ErasedVec::reserve], but returns an error instead of panicking or aborting,
+    /// in which case the vec is unchanged
+    #[inline]
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        let required = self
+            .len
+            .checked_add(additional)
+            .ok_or(TryReserveError::CapacityOverflow)?;
+
+        if required <= self.capacity {
+            return Ok(());
         }
 
-        assert_eq!(vec.len(), 10);
-        assert_eq!(vec.capacity(), 16);
+        // fall back to the exact capacity if rounding up would overflow the layout, when the
+        // exact capacity might still fit
+        let amortised = required
+            .checked_next_power_of_two()
+            .filter(|&capacity| array_layout(self.item.layout, capacity).is_some())
+            .unwrap_or(required);
+        // SAFETY: Zero-sized types have a capacity of `usize::MAX`, so never need to grow
+        unsafe { self.try_grow(amortised) }
+    }
 
-        for i in (0..10).rev() {
-            assert_eq!(*vec.pop().unwrap().as_ref::<i32>(), i);
+    /// The same as [ErasedVec::reserve_exact], but returns an error instead of panicking or
+    /// aborting, in which case the vec is unchanged
+    #[inline]
+    pub fn try_reserve_exact(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        let required = self
+            .len
+            .checked_add(additional)
+            .ok_or(TryReserveError::CapacityOverflow)?;
+
+        if required <= self.capacity {
+            return Ok(());
         }
 
-        assert_eq!(vec.len(), 0);
-        assert_eq!(vec.capacity(), 16);
-
-        assert!(vec.pop().is_none());
-    }
-
-    #[test]
-    fn swap_remove_test() {
-        unsafe { _swap_remove_test() }
+        // SAFETY: Zero-sized types have a capacity of `usize::MAX`, so never need to grow
+        unsafe { self.try_grow(required) }
     }
 
-    unsafe fn _swap_remove_test() {
-        let mut vec = ErasedVec::new::<i32>();
+    /// Shrinks the capacity to the length of the vec, freeing the allocation entirely if it is
+    /// empty. Zero-sized types are unaffected, as they never allocate.
+    ///
+    /// # Panics
+    /// Aborts if the allocator fails to shrink the allocation
+    pub fn shrink_to_fit(&mut self) {
+        if self.item.layout.size() == 0 || self.capacity == self.len {
+            return;
+        }
 
-        assert_eq!(vec.len(), 0);
-        assert_eq!(vec.capacity(), 8);
+        // the allocation is already bigger than this, so its size can't overflow
+        let new_layout = array_layout(self.item.layout, self.len).unwrap();
 
-        for i in 0..10 {
-            vec.push(Ptr::from(&i as *const _ as *mut u8));
+        if new_layout.size() == 0 {
+            // SAFETY: The capacity was bigger than the length, so something was allocated
+            unsafe { self.allocator.deallocate(self.head, self.layout) };
+            self.head = dangling(new_layout);
+        } else {
+            // SAFETY:
+            // - `self.head` was allocated by this allocator with `self.layout`.
+            // - The new layout is smaller but non-zero, with the same alignment.
+            self.head = unsafe { self.allocator.shrink(self.head, self.layout, new_layout) }
+                .unwrap_or_else(|| alloc::handle_alloc_error(new_layout));
         }
 
-        assert_eq!(vec.len(), 10);
+        self.layout = new_layout;
+        self.capacity = self.len;
+    }
+
+    /// # Safety
+    /// The caller must ensure that:
+    /// - The item size is greater than zero (ZST), which is guaranteed if the vec needs to grow
+    /// - The new capacity is greater than the current capacity.
+    unsafe fn try_grow(&mut self, new_capacity: usize) -> Result<(), TryReserveError> {
+        let new_layout = array_layout(self.item.layout, new_capacity)
+            .ok_or(TryReserveError::CapacityOverflow)?;
+
+        let new_head = if self.layout.size() == 0 {
+            // SAFETY: The new layout has a non-zero size, as the item size and new capacity are
+            // greater than zero.
+            unsafe { self.allocator.allocate(new_layout) }
+        } else {
+            // SAFETY:
+            // - `self.head` was allocated by this allocator with `self.layout`.
+            // - The new layout is bigger, with the same alignment.
+            unsafe { self.allocator.grow(self.head, self.layout, new_layout) }
+        };
+
+        // nothing is changed if the allocation fails, as the old block is still valid
+        self.head = new_head.ok_or(TryReserveError::AllocError { layout: new_layout })?;
+        self.layout = new_layout;
+        self.capacity = new_capacity;
+
+        Ok(())
+    }
+}
+
+/// A checked view of an [ErasedVec] as a slice of its item type
+#[derive(Clone, Copy, Debug)]
+pub struct TypedView<'a, T> {
+    slice: &'a [T],
+}
+
+impl<'a, T> TypedView<'a, T> {
+    #[inline]
+    pub fn as_slice(&self) -> &'a [T] {
+        self.slice
+    }
+}
+
+impl<T> Deref for TypedView<'_, T> {
+    type Target = [T];
+
+    #[inline]
+    fn deref(&self) -> &[T] {
+        self.slice
+    }
+}
+
+impl<'a, T> IntoIterator for TypedView<'a, T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.slice.iter()
+    }
+}
+
+/// A checked view of an [ErasedVec] as a mutable slice of its item type
+#[derive(Debug)]
+pub struct TypedViewMut<'a, T> {
+    slice: &'a mut [T],
+}
+
+impl<'a, T> TypedViewMut<'a, T> {
+    #[inline]
+    pub fn into_slice(self) -> &'a mut [T] {
+        self.slice
+    }
+}
+
+impl<T> Deref for TypedViewMut<'_, T> {
+    type Target = [T];
+
+    #[inline]
+    fn deref(&self) -> &[T] {
+        self.slice
+    }
+}
+
+impl<T> DerefMut for TypedViewMut<'_, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut [T] {
+        self.slice
+    }
+}
+
+impl<'a, T> IntoIterator for TypedViewMut<'a, T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.slice.iter_mut()
+    }
+}
+
+/// Iterator over a pointer to each element of an [ErasedVec]
+#[derive(Clone)]
+pub struct PtrIter<'a> {
+    head: NonNull<u8>,
+    /// The size of each element, which is zero for zero-sized types, so every pointer is the same
+    stride: usize,
+    /// Index of the next element from the front
+    front: usize,
+    /// One past the index of the next element from the back
+    back: usize,
+    _marker: PhantomData<&'a ()>,
+}
+
+impl<'a> PtrIter<'a> {
+    /// # Safety
+    /// The caller must ensure that:
+    /// - The index is within the bounds of the vec.
+    #[inline]
+    unsafe fn ptr(&self, index: usize) -> Ptr<'a> {
+        // SAFETY: Within the allocation, as is deferred to the caller
+        Ptr::new(unsafe { self.head.add(index * self.stride) })
+    }
+}
+
+impl<'a> Iterator for PtrIter<'a> {
+    type Item = Ptr<'a>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+
+        // SAFETY: `front` is less than `back`, which is at most the length of the vec
+        let ptr = unsafe { self.ptr(self.front) };
+        self.front += 1;
+        Some(ptr)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
+
+impl DoubleEndedIterator for PtrIter<'_> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+
+        self.back -= 1;
+        // SAFETY: `back` was greater than `front`, and at most the length of the vec
+        Some(unsafe { self.ptr(self.back) })
+    }
+}
+
+impl ExactSizeIterator for PtrIter<'_> {}
+
+impl FusedIterator for PtrIter<'_> {}
+
+/// Iterator over chunks of the elements of an [ErasedVec], as a pointer to the first element of
+/// each chunk and the number of elements in it
+#[derive(Clone)]
+pub struct ChunkPtrIter<'a> {
+    ptrs: PtrIter<'a>,
+    chunk_len: usize,
+}
+
+impl<'a> Iterator for ChunkPtrIter<'a> {
+    type Item = (Ptr<'a>, usize);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let len = self.ptrs.len().min(self.chunk_len);
+        let first = self.ptrs.next()?;
+        // skip over the rest of the chunk
+        self.ptrs.front += len - 1;
+        Some((first, len))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.ptrs.len().div_ceil(self.chunk_len);
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for ChunkPtrIter<'_> {}
+
+impl FusedIterator for ChunkPtrIter<'_> {}
+
+/// Turns a failure to reserve into a panic, or an abort if the allocator failed
+#[inline]
+fn handle_reserve(result: Result<(), TryReserveError>) {
+    match result {
+        Ok(()) => {}
+        Err(TryReserveError::CapacityOverflow) => panic!("Capacity overflow"),
+        Err(TryReserveError::AllocError { layout }) => alloc::handle_alloc_error(layout),
+    }
+}
+
+/// Error returned when an [ErasedVec] fails to reserve more capacity
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TryReserveError {
+    /// The required capacity overflows `usize`, or the size of its allocation overflows `isize`
+    CapacityOverflow,
+    /// The allocator failed to provide a block for the layout
+    AllocError { layout: Layout },
+}
+
+impl fmt::Display for TryReserveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CapacityOverflow => write!(f, "capacity overflow"),
+            Self::AllocError { layout } => write!(
+                f,
+                "failed to allocate {} bytes aligned to {}",
+                layout.size(),
+                layout.align()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TryReserveError {}
+
+impl<A: Allocator> Drop for ErasedVec<A> {
+    fn drop(&mut self) {
+        /// Frees the allocation once the elements are dropped, even if one of their destructors
+        /// panics
+        struct Dealloc<'a, A: Allocator> {
+            allocator: &'a A,
+            head: NonNull<u8>,
+            layout: Layout,
+        }
+
+        impl<A: Allocator> Drop for Dealloc<'_, A> {
+            fn drop(&mut self) {
+                // nothing was allocated for zero-sized types, or a capacity of zero
+                if self.layout.size() != 0 {
+                    unsafe {
+                        self.allocator.deallocate(self.head, self.layout);
+                    }
+                }
+            }
+        }
+
+        let _dealloc = Dealloc {
+            allocator: &self.allocator,
+            head: self.head,
+            layout: self.layout,
+        };
+
+        let len = self.len;
+        self.len = 0;
+
+        // SAFETY: Every element is initialised, and no longer tracked by the vec
+        unsafe { DropGuard::new(&self.item, self.head, 0, len).drop_remaining() };
+    }
+}
+
+/// Drops a range of elements that are no longer tracked by a vec. If one of their destructors
+/// panics, the guard itself is dropped while unwinding, and carries on with the rest.
+struct DropGuard<'a> {
+    item: &'a ErasedType,
+    head: NonNull<u8>,
+    /// The index of the next element to drop
+    next: usize,
+    end: usize,
+}
+
+impl<'a> DropGuard<'a> {
+    #[inline]
+    fn new(item: &'a ErasedType, head: NonNull<u8>, start: usize, end: usize) -> Self {
+        Self {
+            item,
+            head,
+            next: start,
+            end,
+        }
+    }
+
+    /// # Safety
+    /// The caller must ensure that:
+    /// - The elements in the range are initialised, and not dropped by anything else.
+    #[inline]
+    unsafe fn drop_remaining(&mut self) {
+        while self.next < self.end {
+            // SAFETY: Within the range of elements that the guard owns
+            let ptr = unsafe { self.head.add(self.next * self.item.layout.size()) };
+            // move on before dropping, so a panicking element isn't dropped again
+            self.next += 1;
+            // SAFETY: `ptr` is not used after this call
+            unsafe { self.item.dispose(Ptr::new(ptr)) }
+        }
+    }
+}
+
+impl Drop for DropGuard<'_> {
+    fn drop(&mut self) {
+        // only does anything if a destructor panicked part way through
+        // SAFETY: Upheld by the caller of `drop_remaining`
+        unsafe { self.drop_remaining() }
+    }
+}
+
+/// Returns the layout of `n` consecutive elements, or `None` if its size would overflow `isize`
+///
+/// This is equivalent to the unstable `Layout::repeat`, as the element layout is already padded to
+/// its alignment.
+#[inline]
+fn array_layout(item: Layout, n: usize) -> Option<Layout> {
+    debug_assert_eq!(item.size() % item.align(), 0);
+
+    let size = item.size().checked_mul(n)?;
+    Layout::from_size_align(size, item.align()).ok()
+}
+
+/// Returns a non-null pointer that is aligned for the layout, but not valid for any reads or
+/// writes of a non-zero size
+#[inline]
+fn dangling(layout: Layout) -> NonNull<u8> {
+    // SAFETY: Alignment is always non-zero
+    unsafe { NonNull::new_unchecked(core::ptr::without_provenance_mut(layout.align())) }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{
+        mem::ManuallyDrop,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use super::*;
+    use crate::allocator::Counting;
+
+    #[test]
+    fn drop_test() {
+        unsafe { _drop_test() }
+    }
+
+    unsafe fn _drop_test() {
+        let mut vec = ErasedVec::new::<String>();
+        let mut element = ManuallyDrop::new(String::from("Hello World"));
+        vec.push(Ptr::from(&mut element));
+
+        assert_eq!(vec.len(), 1);
+        assert_eq!(vec.capacity(), 8);
+
+        // 'removed' from vec, but still lives there so we are responsible for dropping it.
+        // ... also, this mess of method chaining is to coerce the associated lifetime so I can continue
+        // working on the vec for testing purposes.
+        let element = vec
+            .pop()
+            .unwrap()
+            .as_ptr()
+            .cast::<String>()
+            .as_mut()
+            .unwrap();
+        println!("1: {}", element);
+        assert_eq!(vec.len(), 0);
+        assert_eq!(vec.capacity(), 8);
+
+        // element still not dropped, because vec doesn't know about it
+        vec.clear();
+        println!("2: {}", element);
+
+        // drop the element using vec
+        vec.item.dispose(Ptr::from(element));
+    }
+
+    #[test]
+    fn push_pop_test() {
+        unsafe { _push_pop_test() }
+    }
+
+    unsafe fn _push_pop_test() {
+        let mut vec = ErasedVec::new::<i32>();
+
+        assert_eq!(vec.len(), 0);
+        assert_eq!(vec.capacity(), 8);
+
+        for i in 0..10 {
+            vec.push(Ptr::from(&i as *const _ as *mut u8));
+        }
+
+        assert_eq!(vec.len(), 10);
+        assert_eq!(vec.capacity(), 16);
+
+        for i in (0..10).rev() {
+            assert_eq!(*vec.pop().unwrap().as_ref::<i32>(), i);
+        }
+
+        assert_eq!(vec.len(), 0);
+        assert_eq!(vec.capacity(), 16);
+
+        assert!(vec.pop().is_none());
+    }
+
+    #[test]
+    fn swap_remove_test() {
+        unsafe { _swap_remove_test() }
+    }
+
+    unsafe fn _swap_remove_test() {
+        let mut vec = ErasedVec::new::<i32>();
+
+        assert_eq!(vec.len(), 0);
+        assert_eq!(vec.capacity(), 8);
+
+        for i in 0..10 {
+            vec.push(Ptr::from(&i as *const _ as *mut u8));
+        }
+
+        assert_eq!(vec.len(), 10);
         assert_eq!(vec.capacity(), 16);
 
         // remove the 8th element, which is 7
@@ -621,4 +1550,712 @@ mod tests {
             assert_eq!(*slice[i].get(), i as i32);
         }
     }
+
+    #[test]
+    fn zst_test() {
+        unsafe { _zst_test() }
+    }
+
+    unsafe fn _zst_test() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        #[repr(align(16))]
+        struct Marker;
+
+        impl Drop for Marker {
+            fn drop(&mut self) {
+                DROPS.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let mut vec = ErasedVec::with_capacity::<Marker>(0);
+        assert_eq!(vec.capacity(), usize::MAX);
+        assert!(vec.head.as_ptr().cast::<Marker>().is_aligned());
+
+        for _ in 0..10 {
+            let mut marker = ManuallyDrop::new(Marker);
+            vec.push(Ptr::from(&mut marker));
+        }
+        assert_eq!(vec.len(), 10);
+        assert_eq!(vec.capacity(), usize::MAX);
+        assert_eq!(vec.as_slice::<Marker>().len(), 10);
+
+        vec.swap_remove_drop(3);
+        // out of bounds, so does nothing
+        vec.swap_remove_drop(9);
+        assert_eq!(vec.len(), 9);
+        assert_eq!(DROPS.load(Ordering::Relaxed), 1);
+
+        vec.pop_unchecked().drop_as::<Marker>();
+        assert_eq!(DROPS.load(Ordering::Relaxed), 2);
+
+        // every remaining element is dropped exactly once
+        drop(vec);
+        assert_eq!(DROPS.load(Ordering::Relaxed), 10);
+    }
+
+    #[test]
+    fn zero_capacity_test() {
+        unsafe { _zero_capacity_test() }
+    }
+
+    unsafe fn _zero_capacity_test() {
+        let mut vec = ErasedVec::with_capacity::<u64>(0);
+        assert_eq!(vec.capacity(), 0);
+
+        for i in 0..3u64 {
+            vec.push(Ptr::from(&i as *const _ as *mut u8));
+        }
+        assert_eq!(vec.capacity(), 4);
+        assert_eq!(vec.as_slice::<u64>(), [0, 1, 2]);
+
+        // dropping an unused vec deallocates nothing
+        drop(ErasedVec::with_capacity::<String>(0));
+    }
+
+    #[test]
+    fn push_many_test() {
+        unsafe { _push_many_test() }
+    }
+
+    unsafe fn _push_many_test() {
+        let mut vec = ErasedVec::with_capacity::<String>(2);
+
+        let mut strings = ManuallyDrop::new((0..10).map(|i| i.to_string()).collect::<Vec<_>>());
+        vec.push_many(Ptr::from(strings.as_mut_ptr()), strings.len());
+        // the vec owns the strings now, so only the allocation of the source is freed
+        strings.set_len(0);
+        ManuallyDrop::drop(&mut strings);
+
+        assert_eq!(vec.len(), 10);
+        assert_eq!(vec.capacity(), 16);
+        for (i, string) in vec.as_slice::<String>().iter().enumerate() {
+            assert_eq!(*string, i.to_string());
+        }
+
+        let mut more = ManuallyDrop::new([String::from("a"), String::from("b")]);
+        vec.push_many(Ptr::from(more.as_mut_ptr()), 2);
+        assert_eq!(vec.as_slice::<String>()[9..], ["9", "a", "b"]);
+    }
+
+    #[test]
+    fn extend_test() {
+        unsafe { _extend_test() }
+    }
+
+    unsafe fn _extend_test() {
+        let mut vec = ErasedVec::with_capacity::<u32>(1);
+        vec.extend_from_slice(&[1u32, 2, 3]);
+        vec.extend_from_slice::<u32>(&[]);
+        assert_eq!(vec.as_slice::<u32>(), [1, 2, 3]);
+
+        let mut other = ErasedVec::new::<u32>();
+        other.extend_from_slice(&[4u32, 5]);
+        vec.extend_from_erased_slice(&other);
+        vec.extend_from_erased_slice(&other);
+        assert_eq!(vec.as_slice::<u32>(), [1, 2, 3, 4, 5, 4, 5]);
+        assert_eq!(other.as_slice::<u32>(), [4, 5]);
+
+        let mut markers = ErasedVec::new::<()>();
+        markers.extend_from_slice(&[(); 5]);
+        markers.extend_from_erased_slice(&ErasedVec::new::<()>());
+        assert_eq!(markers.len(), 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot extend a vec from a slice of a different type")]
+    fn extend_wrong_type_test() {
+        ErasedVec::new::<u32>().extend_from_slice(&[1i32]);
+    }
+
+    #[test]
+    fn typed_test() {
+        let mut vec = ErasedVec::new::<String>();
+        vec.push_typed(String::from("a")).unwrap();
+        vec.push_typed(String::from("b")).unwrap();
+
+        assert_eq!(vec.get_typed::<String>(1), Ok(Some(&String::from("b"))));
+        assert_eq!(vec.get_typed::<String>(2), Ok(None));
+        vec.get_typed_mut::<String>(0).unwrap().unwrap().push('!');
+        assert_eq!(vec.as_slice_checked::<String>().unwrap(), ["a!", "b"]);
+
+        assert_eq!(vec.pop_typed::<String>(), Ok(Some(String::from("b"))));
+        assert_eq!(vec.len(), 1);
+
+        let error = vec.push_typed(1u8).unwrap_err();
+        assert_eq!(
+            error,
+            TypeMismatch {
+                expected: TypeId::of::<String>(),
+                found: TypeId::of::<u8>(),
+                found_name: "u8",
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "type mismatch: the vec does not hold elements of type `u8`"
+        );
+        assert!(vec.get_typed::<&str>(0).is_err());
+        assert!(vec.as_slice_checked::<u8>().is_err());
+        assert!(vec.pop_typed::<Vec<u8>>().is_err());
+        assert_eq!(vec.len(), 1);
+
+        let mut markers = ErasedVec::new::<()>();
+        markers.push_typed(()).unwrap();
+        assert_eq!(markers.pop_typed::<()>(), Ok(Some(())));
+        assert_eq!(markers.pop_typed::<()>(), Ok(None));
+    }
+
+    #[test]
+    #[should_panic(expected = "The size of the layout must be a multiple of its alignment")]
+    fn unpadded_layout_test() {
+        // a size that isn't a multiple of the alignment, as some foreign types might declare
+        let layout = Layout::from_size_align(6, 4).unwrap();
+        unsafe { ErasedType::from_raw_parts(TypeId::of::<[u16; 3]>(), layout, |_| {}) };
+    }
+
+    #[test]
+    fn padded_layout_test() {
+        unsafe { _padded_layout_test() }
+    }
+
+    unsafe fn _padded_layout_test() {
+        // the same values, as a type whose layout is padded to be aligned for `u32`
+        #[derive(Debug, PartialEq)]
+        #[repr(C, align(4))]
+        struct Padded([u16; 3]);
+
+        let layout = Layout::from_size_align(6, 4).unwrap().pad_to_align();
+        let item = ErasedType::from_raw_parts(TypeId::of::<Padded>(), layout, |_| {});
+        assert_eq!(item.layout(), Layout::new::<Padded>());
+
+        let mut vec = ErasedVec::with_capacity_erased_type(item, 1);
+        for i in 0..5u16 {
+            let mut value = Padded([i; 3]);
+            vec.push(Ptr::from(&mut value));
+        }
+
+        for i in 0..5 {
+            let ptr = vec.get(i).unwrap();
+            assert!(ptr.is_aligned::<u32>());
+            assert_eq!(*ptr.as_ref::<Padded>(), Padded([i as u16; 3]));
+        }
+        assert_eq!(vec.as_slice_checked::<Padded>().unwrap().len(), 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "Capacity overflow")]
+    fn capacity_overflow_test() {
+        ErasedVec::with_capacity::<u64>(usize::MAX / 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "Capacity overflow")]
+    fn reserve_overflow_test() {
+        let mut vec = ErasedVec::new::<u32>();
+        let mut value = 0u32;
+        // panics before anything is read from the pointer
+        unsafe { vec.push_many(Ptr::from(&mut value), usize::MAX / 2) };
+    }
+
+    fn strings(values: &[&str]) -> ErasedVec {
+        let mut vec = ErasedVec::new::<String>();
+        for value in values {
+            vec.push_typed(value.to_string()).unwrap();
+        }
+        vec
+    }
+
+    #[test]
+    fn insert_remove_test() {
+        unsafe { _insert_remove_test() }
+    }
+
+    unsafe fn _insert_remove_test() {
+        let mut vec = strings(&["b", "d"]);
+
+        let mut value = ManuallyDrop::new(String::from("a"));
+        vec.insert(0, Ptr::from(&mut *value));
+        let mut value = ManuallyDrop::new(String::from("c"));
+        vec.insert(2, Ptr::from(&mut *value));
+        let mut value = ManuallyDrop::new(String::from("e"));
+        vec.insert(4, Ptr::from(&mut *value));
+        assert_eq!(vec.as_slice::<String>(), ["a", "b", "c", "d", "e"]);
+
+        let removed = vec.remove(1).unwrap().as_ptr().cast::<String>().read();
+        assert_eq!(removed, "b");
+        assert_eq!(vec.as_slice::<String>(), ["a", "c", "d", "e"]);
+        assert!(vec.remove(4).is_none());
+
+        vec.remove_drop(0);
+        vec.remove_drop(2);
+        assert_eq!(vec.as_slice::<String>(), ["c", "d"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Insertion index (is 2) should be <= len (is 1)")]
+    fn insert_out_of_bounds_test() {
+        let mut vec = strings(&["a"]);
+        // an empty string doesn't allocate, so nothing leaks when this panics
+        let mut value = ManuallyDrop::new(String::new());
+        unsafe { vec.insert(2, Ptr::from(&mut *value)) };
+    }
+
+    #[test]
+    fn truncate_swap_test() {
+        unsafe { _truncate_swap_test() }
+    }
+
+    unsafe fn _truncate_swap_test() {
+        let mut vec = strings(&["a", "b", "c", "d"]);
+        vec.swap(0, 3);
+        vec.swap(1, 1);
+        assert_eq!(vec.as_slice::<String>(), ["d", "b", "c", "a"]);
+
+        vec.truncate(5);
+        assert_eq!(vec.len(), 4);
+        vec.truncate(2);
+        assert_eq!(vec.as_slice::<String>(), ["d", "b"]);
+        vec.truncate(0);
+        assert!(vec.is_empty());
+    }
+
+    #[test]
+    fn split_off_append_test() {
+        let mut vec = strings(&["a", "b", "c", "d"]);
+
+        let mut tail = vec.split_off(1);
+        assert_eq!(vec.as_slice_checked::<String>().unwrap(), ["a"]);
+        assert_eq!(tail.as_slice_checked::<String>().unwrap(), ["b", "c", "d"]);
+        assert!(vec.split_off(1).is_empty());
+
+        vec.append(&mut tail);
+        assert!(tail.is_empty());
+        assert_eq!(
+            vec.as_slice_checked::<String>().unwrap(),
+            ["a", "b", "c", "d"]
+        );
+
+        // still usable after being emptied
+        tail.push_typed(String::from("e")).unwrap();
+        vec.append(&mut tail);
+        assert_eq!(vec.len(), 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot append a vec of a different type")]
+    fn append_wrong_type_test() {
+        ErasedVec::new::<u32>().append(&mut ErasedVec::new::<i32>());
+    }
+
+    #[test]
+    fn clone_test() {
+        let mut vec = ErasedVec::from_erased_type(ErasedType::new_cloneable::<String>());
+        for value in ["a", "b", "c"] {
+            vec.push_typed(value.to_string()).unwrap();
+        }
+
+        let mut clone = vec.try_clone().unwrap();
+        clone.get_typed_mut::<String>(0).unwrap().unwrap().push('!');
+        assert_eq!(
+            clone.as_slice_checked::<String>().unwrap(),
+            ["a!", "b", "c"]
+        );
+        assert_eq!(vec.as_slice_checked::<String>().unwrap(), ["a", "b", "c"]);
+        assert_eq!(clone.capacity(), 3);
+
+        let mut copies = ErasedVec::from_erased_type(ErasedType::new_copy::<u32>());
+        copies.extend_from_slice(&[1u32, 2, 3]);
+        assert_eq!(
+            copies
+                .try_clone()
+                .unwrap()
+                .as_slice_checked::<u32>()
+                .unwrap(),
+            [1, 2, 3]
+        );
+
+        let empty = ErasedVec::from_erased_type(ErasedType::new_cloneable::<String>());
+        assert!(empty.try_clone().unwrap().is_empty());
+
+        let markers = ErasedVec::from_erased_type(ErasedType::new_copy::<()>());
+        assert!(markers.try_clone().unwrap().is_empty());
+
+        assert_eq!(
+            strings(&["a"]).try_clone().err(),
+            Some(NotCloneable {
+                type_id: TypeId::of::<String>()
+            })
+        );
+    }
+
+    #[test]
+    fn clone_panic_test() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        struct Fragile(u32);
+
+        impl Clone for Fragile {
+            fn clone(&self) -> Self {
+                assert!(self.0 != 2, "can't clone 2");
+                Fragile(self.0)
+            }
+        }
+
+        impl Drop for Fragile {
+            fn drop(&mut self) {
+                DROPS.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let mut vec = ErasedVec::from_erased_type(ErasedType::new_cloneable::<Fragile>());
+        for i in 0..4 {
+            vec.push_typed(Fragile(i)).unwrap();
+        }
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| vec.try_clone()));
+        assert!(result.is_err());
+        // only the two clones that were made are dropped
+        assert_eq!(DROPS.load(Ordering::Relaxed), 2);
+
+        drop(vec);
+        assert_eq!(DROPS.load(Ordering::Relaxed), 6);
+    }
+
+    static PANICKY_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+    /// Panics when dropped if it holds `true`
+    struct Panicky(bool);
+
+    impl Drop for Panicky {
+        fn drop(&mut self) {
+            PANICKY_DROPS.fetch_add(1, Ordering::Relaxed);
+            assert!(!self.0, "dropped a panicky value");
+        }
+    }
+
+    fn panicky(values: &[bool]) -> ErasedVec {
+        let mut vec = ErasedVec::new::<Panicky>();
+        for value in values {
+            vec.push_typed(Panicky(*value)).unwrap();
+        }
+        vec
+    }
+
+    #[test]
+    fn panicking_drop_test() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        PANICKY_DROPS.store(0, Ordering::Relaxed);
+
+        // every element is still dropped exactly once, and the vec is left empty
+        let mut vec = panicky(&[false, true, false, false]);
+        assert!(catch_unwind(AssertUnwindSafe(|| unsafe { vec.clear() })).is_err());
+        assert_eq!(PANICKY_DROPS.load(Ordering::Relaxed), 4);
+        assert!(vec.is_empty());
+
+        // the vec is still usable afterwards
+        vec.push_typed(Panicky(false)).unwrap();
+        drop(vec);
+        assert_eq!(PANICKY_DROPS.load(Ordering::Relaxed), 5);
+
+        let mut vec = panicky(&[false, false, true, false]);
+        assert!(catch_unwind(AssertUnwindSafe(|| unsafe { vec.truncate(1) })).is_err());
+        assert_eq!(PANICKY_DROPS.load(Ordering::Relaxed), 8);
+        assert_eq!(vec.len(), 1);
+
+        let mut vec = panicky(&[true, false, false]);
+        assert!(catch_unwind(AssertUnwindSafe(|| unsafe { vec.swap_remove_drop(0) })).is_err());
+        assert_eq!(PANICKY_DROPS.load(Ordering::Relaxed), 9);
+        assert_eq!(vec.len(), 2);
+        drop(vec);
+        assert_eq!(PANICKY_DROPS.load(Ordering::Relaxed), 11);
+
+        // dropping the vec itself drops everything else, and frees the allocation
+        let vec = panicky(&[false, true, false]);
+        assert!(catch_unwind(AssertUnwindSafe(|| drop(vec))).is_err());
+        assert_eq!(PANICKY_DROPS.load(Ordering::Relaxed), 14);
+    }
+
+    #[test]
+    fn allocator_test() {
+        let allocator = Counting::default();
+
+        let mut vec = ErasedVec::with_capacity_in::<String>(1, &allocator);
+        for i in 0..10 {
+            vec.push_typed(i.to_string()).unwrap();
+        }
+        // grown by allocating and copying each time, as the allocator can't realloc
+        assert_eq!(allocator.allocations.get(), 5);
+        assert_eq!(allocator.live.get(), 1);
+        assert_eq!(vec.get_typed::<String>(9), Ok(Some(&String::from("9"))));
+
+        let tail = vec.split_off(5);
+        let mut bytes = ErasedVec::from_erased_type_in(ErasedType::new_copy::<u8>(), &allocator);
+        bytes.extend_from_slice(&[1u8, 2]);
+        let clone = bytes.try_clone().unwrap();
+        assert_eq!(allocator.live.get(), 4);
+        assert_eq!(
+            tail.as_slice_checked::<String>().unwrap(),
+            ["5", "6", "7", "8", "9"]
+        );
+
+        drop((vec, tail, bytes, clone));
+        assert_eq!(allocator.live.get(), 0);
+
+        // zero-sized types never touch the allocator
+        let mut markers = ErasedVec::new_in::<()>(&allocator);
+        markers.extend_from_slice(&[(); 100]);
+        drop(markers);
+        assert_eq!(allocator.allocations.get(), 8);
+    }
+
+    #[test]
+    fn reserve_test() {
+        let mut vec = ErasedVec::with_capacity::<u32>(0);
+        vec.reserve_exact(5);
+        assert_eq!(vec.capacity(), 5);
+        vec.reserve(6);
+        assert_eq!(vec.capacity(), 8);
+        vec.reserve_exact(3);
+        assert_eq!(vec.capacity(), 8);
+
+        assert_eq!(
+            vec.try_reserve(usize::MAX),
+            Err(TryReserveError::CapacityOverflow)
+        );
+        assert_eq!(
+            vec.try_reserve_exact(usize::MAX / 2),
+            Err(TryReserveError::CapacityOverflow)
+        );
+        assert_eq!(vec.capacity(), 8);
+
+        let mut markers = ErasedVec::new::<()>();
+        assert_eq!(markers.try_reserve(usize::MAX), Ok(()));
+        markers.shrink_to_fit();
+        assert_eq!(markers.capacity(), usize::MAX);
+    }
+
+    #[test]
+    fn try_reserve_alloc_error_test() {
+        /// Only has room for 64 bytes
+        struct Tiny;
+
+        unsafe impl Allocator for Tiny {
+            unsafe fn allocate(&self, layout: Layout) -> Option<NonNull<u8>> {
+                if layout.size() <= 64 {
+                    Global.allocate(layout)
+                } else {
+                    None
+                }
+            }
+
+            unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+                Global.deallocate(ptr, layout)
+            }
+        }
+
+        let mut vec = ErasedVec::with_capacity_in::<u64>(2, Tiny);
+        vec.extend_from_slice(&[1u64, 2]);
+        assert_eq!(vec.try_reserve_exact(6), Ok(()));
+
+        let error = vec.try_reserve_exact(7).unwrap_err();
+        assert_eq!(
+            error,
+            TryReserveError::AllocError {
+                layout: Layout::array::<u64>(9).unwrap()
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "failed to allocate 72 bytes aligned to 8"
+        );
+
+        // the vec is untouched by the failure
+        assert_eq!(vec.capacity(), 8);
+        assert_eq!(vec.as_slice_checked::<u64>().unwrap(), [1, 2]);
+
+        // rounding up to a power of two would overflow, but the exact capacity is still tried
+        let mut bytes = ErasedVec::new_in::<u8>(Tiny);
+        let additional = (isize::MAX as usize) / 2 + 2;
+        assert_eq!(
+            bytes.try_reserve(additional),
+            Err(TryReserveError::AllocError {
+                layout: Layout::array::<u8>(additional).unwrap()
+            })
+        );
+        assert_eq!(
+            bytes.try_reserve(isize::MAX as usize + 1),
+            Err(TryReserveError::CapacityOverflow)
+        );
+    }
+
+    #[test]
+    fn shrink_to_fit_test() {
+        let allocator = Counting::default();
+
+        let mut vec = ErasedVec::with_capacity_in::<String>(16, &allocator);
+        for value in ["a", "b", "c"] {
+            vec.push_typed(value.to_string()).unwrap();
+        }
+
+        vec.shrink_to_fit();
+        assert_eq!(vec.capacity(), 3);
+        assert_eq!(vec.as_slice_checked::<String>().unwrap(), ["a", "b", "c"]);
+        assert_eq!(allocator.live.get(), 1);
+
+        // an empty vec frees its allocation, but can still grow again
+        unsafe { vec.clear() };
+        vec.shrink_to_fit();
+        assert_eq!(vec.capacity(), 0);
+        assert_eq!(allocator.live.get(), 0);
+
+        vec.push_typed(String::from("d")).unwrap();
+        assert_eq!(vec.capacity(), 1);
+        drop(vec);
+        assert_eq!(allocator.live.get(), 0);
+    }
+
+    #[test]
+    fn view_test() {
+        let mut vec = strings(&["a", "b", "c"]);
+
+        let view = vec.view::<String>().unwrap();
+        assert_eq!(view.len(), 3);
+        assert_eq!(view[1], "b");
+        assert_eq!(
+            view.into_iter().map(String::as_str).collect::<String>(),
+            "abc"
+        );
+        assert!(vec.view::<&str>().is_none());
+
+        let mut view = vec.view_mut::<String>().unwrap();
+        view[0].push('!');
+        view.reverse();
+        for string in view {
+            string.push('?');
+        }
+        assert_eq!(*vec.view::<String>().unwrap(), ["c?", "b?", "a!?"]);
+        assert!(vec.view_mut::<u8>().is_none());
+    }
+
+    #[test]
+    fn iter_ptrs_test() {
+        unsafe { _iter_ptrs_test() }
+    }
+
+    unsafe fn _iter_ptrs_test() {
+        let mut vec = ErasedVec::new::<u16>();
+        vec.extend_from_slice(&[1u16, 2, 3, 4, 5]);
+
+        let values = vec.iter_ptrs().map(|ptr| *ptr.as_ref::<u16>());
+        assert_eq!(values.collect::<Vec<_>>(), [1, 2, 3, 4, 5]);
+
+        let mut ptrs = vec.iter_ptrs();
+        assert_eq!(ptrs.len(), 5);
+        assert_eq!(*ptrs.next_back().unwrap().as_ref::<u16>(), 5);
+        assert_eq!(*ptrs.next().unwrap().as_ref::<u16>(), 1);
+        assert_eq!(ptrs.len(), 3);
+        assert_eq!(ptrs.rev().map(|ptr| *ptr.as_ref::<u16>()).sum::<u16>(), 9);
+
+        let mut markers = ErasedVec::new::<()>();
+        markers.extend_from_slice(&[(); 3]);
+        assert_eq!(markers.iter_ptrs().count(), 3);
+        assert!(ErasedVec::new::<u8>().iter_ptrs().next().is_none());
+    }
+
+    #[test]
+    fn iter_chunk_ptrs_test() {
+        unsafe { _iter_chunk_ptrs_test() }
+    }
+
+    unsafe fn _iter_chunk_ptrs_test() {
+        let mut vec = ErasedVec::new::<u32>();
+        vec.extend_from_slice(&(0..10u32).collect::<Vec<_>>());
+
+        let chunks = vec.iter_chunk_ptrs(4);
+        assert_eq!(chunks.len(), 3);
+        let chunks = chunks
+            .map(|(ptr, len)| core::slice::from_raw_parts(ptr.as_ptr().cast::<u32>(), len).to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(chunks, [vec![0, 1, 2, 3], vec![4, 5, 6, 7], vec![8, 9]]);
+
+        assert_eq!(
+            vec.iter_chunk_ptrs(5)
+                .map(|(_, len)| len)
+                .collect::<Vec<_>>(),
+            [5, 5]
+        );
+        assert_eq!(vec.iter_chunk_ptrs(20).count(), 1);
+        assert_eq!(ErasedVec::new::<u32>().iter_chunk_ptrs(4).count(), 0);
+    }
+
+    #[test]
+    fn forged_type_test() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        // a type id paired with a layout too small for it, which breaks the contract of
+        // `from_raw_parts`, but must still never be trusted by the typed API
+        let item = unsafe {
+            ErasedType::from_raw_parts(TypeId::of::<[u64; 8]>(), Layout::new::<u8>(), |_| {})
+        };
+        assert!(!item.is::<[u64; 8]>());
+
+        let mut vec = ErasedVec::from_erased_type(item);
+        for _ in 0..4 {
+            assert!(vec.push_typed([0xAAu64; 8]).is_err());
+        }
+        assert!(vec.is_empty());
+        assert!(vec.get_typed::<[u64; 8]>(0).is_err());
+        assert!(vec.pop_typed::<[u64; 8]>().is_err());
+        assert!(vec.as_slice_checked::<[u64; 8]>().is_err());
+        assert!(vec.as_slice_mut_checked::<[u64; 8]>().is_err());
+
+        let result = catch_unwind(AssertUnwindSafe(|| vec.extend_from_slice(&[[0u64; 8]])));
+        assert!(result.is_err());
+
+        let mut real = ErasedVec::new::<[u64; 8]>();
+        real.push_typed([1u64; 8]).unwrap();
+        let result = catch_unwind(AssertUnwindSafe(|| vec.append(&mut real)));
+        assert!(result.is_err());
+        assert!(vec.is_empty());
+        assert_eq!(real.len(), 1);
+    }
+
+    #[test]
+    fn raw_parts_clone_test() {
+        let item = unsafe {
+            ErasedType::from_raw_parts_cloneable(
+                TypeId::of::<String>(),
+                Layout::new::<String>(),
+                |ptr| ptr.drop_as::<String>(),
+                |src, dst| {
+                    dst.as_ptr()
+                        .cast::<String>()
+                        .write(src.as_ref::<String>().clone())
+                },
+            )
+        };
+        assert!(item.is_cloneable());
+
+        let mut vec = ErasedVec::from_erased_type(item);
+        vec.push_typed(String::from("a")).unwrap();
+        vec.push_typed(String::from("b")).unwrap();
+
+        let clone = vec.try_clone().unwrap();
+        assert_eq!(clone.as_slice_checked::<String>().unwrap(), ["a", "b"]);
+    }
+
+    #[test]
+    fn forged_type_view_test() {
+        // breaks the contract of `from_raw_parts`, as in `forged_type_test`
+        let item = unsafe {
+            ErasedType::from_raw_parts(TypeId::of::<[u64; 8]>(), Layout::new::<u8>(), |_| {})
+        };
+        let mut vec = ErasedVec::from_erased_type(item);
+        unsafe { vec.push(Ptr::from(&mut 0u8)) };
+
+        assert!(vec.view::<[u64; 8]>().is_none());
+        assert!(vec.view_mut::<[u64; 8]>().is_none());
+    }
 }
@@ -0,0 +1,461 @@
+use core::{
+    fmt,
+    hash::{Hash, Hasher},
+};
+
+use crate::bitset::{BitIndexOutOfBounds, Element, BITS_PER_ELEMENT};
+
+/// The number of layers in a [HierarchicalBitSet], including the bottom layer that holds the bits
+/// themselves.
+const LAYERS: usize = 4;
+const TOP: usize = LAYERS - 1;
+/// The number of bits that can be stored in a [HierarchicalBitSet], which is the number of bits
+/// that the single element of the top layer can summarise.
+pub const HIERARCHICAL_BITSET_CAPACITY: usize = BITS_PER_ELEMENT.pow(LAYERS as u32);
+
+/// A source of layered bits, where each bit in an upper layer summarises whether the corresponding
+/// element in the layer below may have any bits set.
+///
+/// A summary bit may be set for an element that turns out to be empty (e.g. for an intersection of
+/// sets), but a summary bit must never be clear for an element that has bits set.
+pub trait BitLayers {
+    /// Returns the element at `index` within `layer`, where layer 0 holds the bits themselves.
+    /// Elements beyond the end of a layer are clear.
+    fn element(&self, layer: usize, index: usize) -> Element;
+
+    fn test(&self, index: usize) -> bool {
+        let (i, j) = (index / BITS_PER_ELEMENT, index % BITS_PER_ELEMENT);
+        self.element(0, i) & (1 << j) != 0
+    }
+
+    fn is_empty(&self) -> bool {
+        self.iter_indices().next().is_none()
+    }
+
+    /// Counts the set bits, only visiting elements of the bottom layer that are summarised as
+    /// having bits set
+    fn count_ones(&self) -> usize {
+        LayerIter::new(self, 1)
+            .map(|i| self.element(0, i).count_ones() as usize)
+            .sum()
+    }
+
+    /// Iterates over the indices of set bits, skipping any regions that are summarised as empty
+    fn iter_indices(&self) -> LayerIter<'_, Self> {
+        LayerIter::new(self, 0)
+    }
+}
+
+impl<T: BitLayers + ?Sized> BitLayers for &T {
+    #[inline]
+    fn element(&self, layer: usize, index: usize) -> Element {
+        (**self).element(layer, index)
+    }
+}
+
+/// A bitset made of layers, where each bit in an upper layer summarises an element in the layer
+/// below. This makes iteration and emptiness checks skip empty regions of the set entirely, which
+/// makes it suited to large, sparse sets.
+///
+/// The set grows to fit any bit that is set, up to [HIERARCHICAL_BITSET_CAPACITY] bits.
+#[derive(Clone)]
+pub struct HierarchicalBitSet {
+    /// Layer 0 holds the bits themselves, and each layer above it has one bit per element in the
+    /// layer below, which is set if that element has any bits set.
+    layers: [Vec<Element>; LAYERS],
+}
+
+impl HierarchicalBitSet {
+    pub fn new() -> Self {
+        Self {
+            layers: core::array::from_fn(|_| Vec::new()),
+        }
+    }
+
+    /// # Panics
+    /// Panics if `index` is not less than [HIERARCHICAL_BITSET_CAPACITY]
+    pub fn from_index(index: usize) -> Self {
+        let mut bit_set = Self::new();
+        bit_set.set(index);
+        bit_set
+    }
+
+    /// # Panics
+    /// Panics if `index` is not less than [HIERARCHICAL_BITSET_CAPACITY]
+    pub fn set(&mut self, index: usize) {
+        if index >= HIERARCHICAL_BITSET_CAPACITY {
+            let capacity = HIERARCHICAL_BITSET_CAPACITY;
+            panic!("{}", BitIndexOutOfBounds { index, capacity });
+        }
+
+        let mut index = index;
+        for layer in self.layers.iter_mut() {
+            let (i, j) = (index / BITS_PER_ELEMENT, index % BITS_PER_ELEMENT);
+
+            if i >= layer.len() {
+                layer.resize(i + 1, 0);
+            }
+            layer[i] |= 1 << j;
+
+            // the summary bit for this element in the next layer up
+            index = i;
+        }
+    }
+
+    /// Clears the bit at `index`, which does nothing if it is beyond the end of the set
+    pub fn clear(&mut self, index: usize) {
+        let mut index = index;
+        for layer in self.layers.iter_mut() {
+            let (i, j) = (index / BITS_PER_ELEMENT, index % BITS_PER_ELEMENT);
+
+            let Some(bits) = layer.get_mut(i) else {
+                return;
+            };
+            *bits &= !(1 << j);
+
+            // the summary bit above is only cleared once the whole element is empty
+            if *bits != 0 {
+                return;
+            }
+            index = i;
+        }
+    }
+
+    /// Returns whether the bit at `index` is set, which is never the case if it is beyond the end
+    /// of the set
+    #[inline]
+    pub fn test(&self, index: usize) -> bool {
+        BitLayers::test(self, index)
+    }
+
+    /// Clears every bit, without releasing any memory
+    pub fn clear_all(&mut self) {
+        for layer in self.layers.iter_mut() {
+            layer.fill(0);
+        }
+    }
+
+    /// Returns true if no bits are set. This only needs to check the top layer.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.layers[TOP].first().is_none_or(|bits| *bits == 0)
+    }
+
+    #[inline]
+    pub fn count_ones(&self) -> usize {
+        BitLayers::count_ones(self)
+    }
+
+    #[inline]
+    pub fn iter_indices(&self) -> LayerIter<'_, Self> {
+        BitLayers::iter_indices(self)
+    }
+}
+
+impl Default for HierarchicalBitSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BitLayers for HierarchicalBitSet {
+    #[inline]
+    fn element(&self, layer: usize, index: usize) -> Element {
+        self.layers[layer].get(index).copied().unwrap_or(0)
+    }
+}
+
+impl HierarchicalBitSet {
+    /// The bottom layer without any trailing empty elements, which are left behind when bits are
+    /// cleared. The upper layers are derived from it, so this is all that sets need to compare.
+    fn trimmed_bits(&self) -> &[Element] {
+        let bits = &self.layers[0];
+        let len = bits
+            .iter()
+            .rposition(|bits| *bits != 0)
+            .map_or(0, |i| i + 1);
+        &bits[..len]
+    }
+}
+
+impl PartialEq for HierarchicalBitSet {
+    fn eq(&self, other: &Self) -> bool {
+        self.trimmed_bits() == other.trimmed_bits()
+    }
+}
+
+impl Eq for HierarchicalBitSet {}
+
+impl Hash for HierarchicalBitSet {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.trimmed_bits().hash(state);
+    }
+}
+
+impl fmt::Debug for HierarchicalBitSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter_indices()).finish()
+    }
+}
+
+/// A lazy intersection of several layered bitsets, which is only evaluated as it is iterated.
+///
+/// An intersection of no sets is empty.
+pub struct HierarchicalAnd<'a, S> {
+    sets: &'a [S],
+}
+
+impl<'a, S: BitLayers> HierarchicalAnd<'a, S> {
+    pub fn new(sets: &'a [S]) -> Self {
+        Self { sets }
+    }
+}
+
+impl<'a, S: BitLayers> BitLayers for HierarchicalAnd<'a, S> {
+    #[inline]
+    fn element(&self, layer: usize, index: usize) -> Element {
+        match self.sets.split_first() {
+            Some((first, rest)) => rest.iter().fold(first.element(layer, index), |bits, set| {
+                bits & set.element(layer, index)
+            }),
+            None => 0,
+        }
+    }
+}
+
+/// A lazy union of several layered bitsets, which is only evaluated as it is iterated
+pub struct HierarchicalOr<'a, S> {
+    sets: &'a [S],
+}
+
+impl<'a, S: BitLayers> HierarchicalOr<'a, S> {
+    pub fn new(sets: &'a [S]) -> Self {
+        Self { sets }
+    }
+}
+
+impl<'a, S: BitLayers> BitLayers for HierarchicalOr<'a, S> {
+    #[inline]
+    fn element(&self, layer: usize, index: usize) -> Element {
+        self.sets
+            .iter()
+            .fold(0, |bits, set| bits | set.element(layer, index))
+    }
+}
+
+/// Iterator over the indices of set bits within a layer of a [BitLayers] source, which descends
+/// from the top layer and skips any elements that are summarised as empty.
+pub struct LayerIter<'a, S: ?Sized> {
+    source: &'a S,
+    /// The remaining bits to visit in the current element of each layer
+    masks: [Element; LAYERS],
+    /// The index of the current element of each layer
+    indices: [usize; LAYERS],
+    /// The layer currently being visited
+    depth: usize,
+    /// The layer whose bit indices are yielded
+    bottom: usize,
+}
+
+impl<'a, S: BitLayers + ?Sized> LayerIter<'a, S> {
+    fn new(source: &'a S, bottom: usize) -> Self {
+        let mut masks = [0; LAYERS];
+        masks[TOP] = source.element(TOP, 0);
+
+        Self {
+            source,
+            masks,
+            indices: [0; LAYERS],
+            depth: TOP,
+            bottom,
+        }
+    }
+}
+
+impl<'a, S: BitLayers + ?Sized> Iterator for LayerIter<'a, S> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let mask = self.masks[self.depth];
+
+            if mask == 0 {
+                // this element is exhausted, so continue with the layer above
+                if self.depth == TOP {
+                    return None;
+                }
+                self.depth += 1;
+                continue;
+            }
+
+            // clears the lowest significant bit
+            self.masks[self.depth] = mask & mask.wrapping_sub(1);
+            let index =
+                self.indices[self.depth] * BITS_PER_ELEMENT + mask.trailing_zeros() as usize;
+
+            if self.depth == self.bottom {
+                return Some(index);
+            }
+
+            // descend into the element that this bit summarises
+            self.depth -= 1;
+            self.indices[self.depth] = index;
+            self.masks[self.depth] = self.source.element(self.depth, index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+
+    fn sample(step: usize, offset: usize) -> (HierarchicalBitSet, BTreeSet<usize>) {
+        let indices: BTreeSet<usize> = (offset..300_000).step_by(step).collect();
+
+        let mut bit_set = HierarchicalBitSet::new();
+        for &index in &indices {
+            bit_set.set(index);
+        }
+
+        (bit_set, indices)
+    }
+
+    #[test]
+    fn test_hierarchical_bitset() {
+        let mut bit_set = HierarchicalBitSet::new();
+        assert!(bit_set.is_empty());
+        assert!(!bit_set.test(1_000_000));
+
+        bit_set.set(17);
+        bit_set.set(64 * 64 * 64 + 3);
+        assert!(bit_set.test(17));
+        assert!(bit_set.test(64 * 64 * 64 + 3));
+        assert!(!bit_set.test(18));
+        assert!(!bit_set.is_empty());
+        assert_eq!(bit_set.count_ones(), 2);
+
+        let mut iter = bit_set.iter_indices();
+        assert_eq!(iter.next(), Some(17));
+        assert_eq!(iter.next(), Some(64 * 64 * 64 + 3));
+        assert_eq!(iter.next(), None);
+
+        // the summary bits are cleared once their element is empty
+        bit_set.clear(17);
+        bit_set.clear(64 * 64 * 64 + 3);
+        assert!(bit_set.is_empty());
+        assert_eq!(bit_set.iter_indices().next(), None);
+
+        // clearing beyond the end of the set does nothing
+        bit_set.clear(HIERARCHICAL_BITSET_CAPACITY - 1);
+    }
+
+    #[test]
+    fn test_hierarchical_bitset_partial_clear() {
+        let mut bit_set = HierarchicalBitSet::new();
+        bit_set.set(1);
+        bit_set.set(2);
+        bit_set.clear(1);
+
+        // the element still has a bit set, so it must remain visible in the summary
+        assert!(!bit_set.is_empty());
+        assert_eq!(bit_set.iter_indices().collect::<Vec<_>>(), vec![2]);
+
+        bit_set.set(5000);
+        bit_set.clear_all();
+        assert!(bit_set.is_empty());
+        assert_eq!(bit_set.count_ones(), 0);
+    }
+
+    #[test]
+    fn test_hierarchical_bitset_iter() {
+        let (bit_set, indices) = sample(97, 5);
+
+        assert_eq!(bit_set.count_ones(), indices.len());
+        assert!(bit_set.iter_indices().eq(indices.iter().copied()));
+
+        let last = HIERARCHICAL_BITSET_CAPACITY - 1;
+        let bit_set = HierarchicalBitSet::from_index(last);
+        assert_eq!(bit_set.iter_indices().collect::<Vec<_>>(), vec![last]);
+    }
+
+    #[test]
+    fn test_hierarchical_bitset_eq() {
+        use std::hash::{BuildHasher, RandomState};
+
+        let mut bit_set = HierarchicalBitSet::new();
+        bit_set.set(5000);
+        bit_set.clear(5000);
+
+        // the layers have grown, but no bits are set
+        let empty = HierarchicalBitSet::new();
+        assert_eq!(bit_set, empty);
+        let hasher = RandomState::new();
+        assert_eq!(hasher.hash_one(&bit_set), hasher.hash_one(&empty));
+
+        bit_set.set(3);
+        assert_ne!(bit_set, empty);
+        assert_eq!(bit_set, HierarchicalBitSet::from_index(3));
+        assert_eq!(
+            hasher.hash_one(&bit_set),
+            hasher.hash_one(HierarchicalBitSet::from_index(3))
+        );
+
+        bit_set.set(64);
+        assert_eq!(format!("{bit_set:?}"), "{3, 64}");
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_hierarchical_bitset_out_of_bounds() {
+        HierarchicalBitSet::new().set(HIERARCHICAL_BITSET_CAPACITY);
+    }
+
+    #[test]
+    fn test_hierarchical_views() {
+        let (a, a_indices) = sample(3, 0);
+        let (b, b_indices) = sample(5, 1);
+        let (c, c_indices) = sample(4096, 7);
+
+        let sets = [&a, &b, &c];
+
+        let and = HierarchicalAnd::new(&sets);
+        let expected: Vec<usize> = a_indices
+            .iter()
+            .filter(|i| b_indices.contains(i) && c_indices.contains(i))
+            .copied()
+            .collect();
+        assert!(and.iter_indices().eq(expected.iter().copied()));
+        assert_eq!(and.count_ones(), expected.len());
+        assert_eq!(and.is_empty(), expected.is_empty());
+
+        let or = HierarchicalOr::new(&sets);
+        let expected: BTreeSet<usize> = a_indices
+            .union(&b_indices)
+            .chain(&c_indices)
+            .copied()
+            .collect();
+        assert!(or.iter_indices().eq(expected.iter().copied()));
+        assert_eq!(or.count_ones(), expected.len());
+        assert!(or.test(3));
+        assert!(!or.test(2));
+
+        // summaries overlap, but the bits themselves don't
+        let mut d = HierarchicalBitSet::new();
+        d.set(0);
+        let mut e = HierarchicalBitSet::new();
+        e.set(1);
+        let sets = [&d, &e];
+        assert!(HierarchicalAnd::new(&sets).is_empty());
+
+        // views compose
+        let ors = [HierarchicalOr::new(&sets), HierarchicalOr::new(&sets)];
+        let and_of_ors = HierarchicalAnd::new(&ors);
+        assert_eq!(and_of_ors.iter_indices().collect::<Vec<_>>(), vec![0, 1]);
+
+        let none: [&HierarchicalBitSet; 0] = [];
+        assert!(HierarchicalAnd::new(&none).is_empty());
+        assert!(HierarchicalOr::new(&none).is_empty());
+    }
+}
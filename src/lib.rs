@@ -1,15 +1,29 @@
+mod allocator;
 mod array_queue;
 mod array_vec;
+mod atomic_bitset;
+mod bit_vec;
 mod bitset;
+#[cfg(feature = "serde")]
+mod bitset_serde;
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod bitset_simd;
 mod erased_vec;
+mod hierarchical_bitset;
 mod sparse_map;
 mod spsc_channel;
 mod store;
+mod table;
 
+pub use allocator::*;
 pub use array_queue::*;
 pub use array_vec::*;
+pub use atomic_bitset::*;
+pub use bit_vec::*;
 pub use bitset::*;
 pub use erased_vec::*;
+pub use hierarchical_bitset::*;
 pub use sparse_map::*;
 pub use spsc_channel::*;
 pub use store::*;
+pub use table::*;
@@ -1,3 +1,9 @@
+use core::{
+    fmt,
+    iter::FusedIterator,
+    ops::{Index as IndexOp, IndexMut},
+};
+
 #[derive(Clone, Copy)]
 enum Index {
     Free,
@@ -21,6 +27,7 @@ impl Index {
     }
 }
 
+#[derive(Clone)]
 pub struct SparseMap<T> {
     /// A packed collection of stored items.
     items: Vec<T>,
@@ -184,6 +191,24 @@ impl<T> SparseMap<T> {
     pub fn keys(&self) -> &[usize] {
         self.keys.as_slice()
     }
+
+    /// Iterates over `(key, &item)` pairs in dense order
+    #[inline]
+    pub fn iter(&self) -> SparseMapIter<'_, T> {
+        SparseMapIter {
+            keys: self.keys.iter(),
+            items: self.items.iter(),
+        }
+    }
+
+    /// Iterates over `(key, &mut item)` pairs in dense order
+    #[inline]
+    pub fn iter_mut(&mut self) -> SparseMapIterMut<'_, T> {
+        SparseMapIterMut {
+            keys: self.keys.iter(),
+            items: self.items.iter_mut(),
+        }
+    }
 }
 
 impl<T> Default for SparseMap<T> {
@@ -192,6 +217,163 @@ impl<T> Default for SparseMap<T> {
     }
 }
 
+impl<T> IndexOp<usize> for SparseMap<T> {
+    type Output = T;
+
+    #[inline]
+    fn index(&self, key: usize) -> &Self::Output {
+        self.get(key).unwrap()
+    }
+}
+
+impl<T> IndexMut<usize> for SparseMap<T> {
+    #[inline]
+    fn index_mut(&mut self, key: usize) -> &mut Self::Output {
+        self.get_mut(key).unwrap()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SparseMap<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// Two maps are equal if they contain the same key/item pairs, regardless of the order in which
+/// the items are densely packed.
+impl<T: PartialEq> PartialEq for SparseMap<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .all(|(key, item)| other.get(key).is_some_and(|other| item == other))
+    }
+}
+
+impl<T: Eq> Eq for SparseMap<T> {}
+
+impl<T> Extend<(usize, T)> for SparseMap<T> {
+    fn extend<I: IntoIterator<Item = (usize, T)>>(&mut self, iter: I) {
+        for (key, item) in iter {
+            self.insert(key, item);
+        }
+    }
+}
+
+impl<T> FromIterator<(usize, T)> for SparseMap<T> {
+    fn from_iter<I: IntoIterator<Item = (usize, T)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<T> IntoIterator for SparseMap<T> {
+    type Item = (usize, T);
+    type IntoIter = SparseMapIntoIter<T>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        SparseMapIntoIter {
+            keys: self.keys.into_iter(),
+            items: self.items.into_iter(),
+        }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a SparseMap<T> {
+    type Item = (usize, &'a T);
+    type IntoIter = SparseMapIter<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut SparseMap<T> {
+    type Item = (usize, &'a mut T);
+    type IntoIter = SparseMapIterMut<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// Iterator over `(key, &item)` pairs of a [SparseMap]
+pub struct SparseMapIter<'a, T> {
+    keys: core::slice::Iter<'a, usize>,
+    items: core::slice::Iter<'a, T>,
+}
+
+/// Iterator over `(key, &mut item)` pairs of a [SparseMap]
+pub struct SparseMapIterMut<'a, T> {
+    keys: core::slice::Iter<'a, usize>,
+    items: core::slice::IterMut<'a, T>,
+}
+
+/// Owning iterator over `(key, item)` pairs of a [SparseMap]
+pub struct SparseMapIntoIter<T> {
+    keys: std::vec::IntoIter<usize>,
+    items: std::vec::IntoIter<T>,
+}
+
+macro_rules! impl_iterator {
+    ( $iter:ty, $item:ty, $( $lt:lifetime )? ) => {
+        impl<$( $lt, )? T> Iterator for $iter {
+            type Item = (usize, $item);
+
+            #[inline]
+            fn next(&mut self) -> Option<Self::Item> {
+                Some((*self.keys.next()?, self.items.next()?))
+            }
+
+            #[inline]
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                self.items.size_hint()
+            }
+        }
+
+        impl<$( $lt, )? T> DoubleEndedIterator for $iter {
+            #[inline]
+            fn next_back(&mut self) -> Option<Self::Item> {
+                Some((*self.keys.next_back()?, self.items.next_back()?))
+            }
+        }
+
+        impl<$( $lt, )? T> ExactSizeIterator for $iter {}
+        impl<$( $lt, )? T> FusedIterator for $iter {}
+    };
+}
+
+impl_iterator!(SparseMapIter<'a, T>, &'a T, 'a);
+impl_iterator!(SparseMapIterMut<'a, T>, &'a mut T, 'a);
+
+impl<T> Iterator for SparseMapIntoIter<T> {
+    type Item = (usize, T);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        Some((self.keys.next()?, self.items.next()?))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.items.size_hint()
+    }
+}
+
+impl<T> DoubleEndedIterator for SparseMapIntoIter<T> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        Some((self.keys.next_back()?, self.items.next_back()?))
+    }
+}
+
+impl<T> ExactSizeIterator for SparseMapIntoIter<T> {}
+impl<T> FusedIterator for SparseMapIntoIter<T> {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -272,4 +454,74 @@ mod tests {
         assert_eq!(sparse_set.contains_key(1), false);
         assert_eq!(sparse_set.contains_key(2), true);
     }
+
+    #[test]
+    fn test_index() {
+        let mut sparse_set: SparseMap<u32> = SparseMap::new();
+        sparse_set.insert(0, 10);
+        sparse_set.insert(2, 20);
+
+        assert_eq!(sparse_set[0], 10);
+        assert_eq!(sparse_set[2], 20);
+
+        sparse_set[2] = 30;
+        assert_eq!(sparse_set[2], 30);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_index_missing() {
+        let sparse_set: SparseMap<u32> = SparseMap::new();
+        let _ = sparse_set[1];
+    }
+
+    #[test]
+    fn test_iter() {
+        let mut sparse_set: SparseMap<u32> = SparseMap::new();
+        sparse_set.insert(3, 10);
+        sparse_set.insert(0, 20);
+
+        let mut iter = sparse_set.iter();
+        assert_eq!(iter.next(), Some((3, &10)));
+        assert_eq!(iter.next(), Some((0, &20)));
+        assert_eq!(iter.next(), None);
+
+        for (key, item) in &mut sparse_set {
+            *item += key as u32;
+        }
+        assert_eq!(sparse_set.get(3), Some(&13));
+        assert_eq!(sparse_set.get(0), Some(&20));
+
+        let items: Vec<(usize, u32)> = sparse_set.into_iter().collect();
+        assert_eq!(items, vec![(3, 13), (0, 20)]);
+    }
+
+    #[test]
+    fn test_from_iter_extend() {
+        let mut sparse_set: SparseMap<u32> = [(1, 10), (12, 20)].into_iter().collect();
+        assert_eq!(sparse_set.len(), 2);
+        assert_eq!(sparse_set.get(12), Some(&20));
+
+        sparse_set.extend([(1, 30), (4, 40)]);
+        assert_eq!(sparse_set.len(), 3);
+        assert_eq!(sparse_set.get(1), Some(&30));
+        assert_eq!(sparse_set.get(4), Some(&40));
+    }
+
+    #[test]
+    fn test_eq_ignores_order() {
+        let a: SparseMap<u32> = [(0, 10), (5, 50)].into_iter().collect();
+        let b: SparseMap<u32> = [(5, 50), (0, 10)].into_iter().collect();
+        let c: SparseMap<u32> = [(5, 50), (0, 11)].into_iter().collect();
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_eq!(a.clone(), a);
+    }
+
+    #[test]
+    fn test_debug() {
+        let sparse_set: SparseMap<u32> = [(2, 10), (0, 20)].into_iter().collect();
+        assert_eq!(format!("{:?}", sparse_set), "{2: 10, 0: 20}");
+    }
 }
@@ -0,0 +1,505 @@
+use core::{any::TypeId, mem::ManuallyDrop};
+
+use crate::{ErasedType, ErasedVec, Ptr};
+
+/// A set of [ErasedVec] columns, one per type, that always have the same number of rows. This is
+/// the storage of an archetype in an ECS, where each row is an entity and each column a component.
+///
+/// Columns are kept sorted by [TypeId], so tables with some of the same types line up.
+pub struct Table {
+    columns: Vec<ErasedVec>,
+    len: usize,
+}
+
+impl Table {
+    /// # Panics
+    /// Panics if two of the types are the same
+    pub fn new(types: impl IntoIterator<Item = ErasedType>) -> Self {
+        Self::with_capacity(types, 0)
+    }
+
+    /// # Panics
+    /// Panics if two of the types are the same
+    pub fn with_capacity(types: impl IntoIterator<Item = ErasedType>, capacity: usize) -> Self {
+        let mut columns = types
+            .into_iter()
+            .map(|item| ErasedVec::with_capacity_erased_type(item, capacity))
+            .collect::<Vec<_>>();
+        columns.sort_unstable_by_key(|column| column.erased_type().type_id());
+
+        assert!(
+            columns
+                .windows(2)
+                .all(|pair| pair[0].erased_type().type_id() != pair[1].erased_type().type_id()),
+            "A table cannot have two columns of the same type"
+        );
+
+        Self { columns, len: 0 }
+    }
+
+    /// The number of rows
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[inline]
+    pub fn column_count(&self) -> usize {
+        self.columns.len()
+    }
+
+    /// The types of the columns, in the order that they are stored
+    pub fn type_ids(&self) -> impl Iterator<Item = TypeId> + '_ {
+        self.columns
+            .iter()
+            .map(|column| column.erased_type().type_id())
+    }
+
+    #[inline]
+    pub fn has_column(&self, type_id: TypeId) -> bool {
+        self.column_index(type_id).is_some()
+    }
+
+    #[inline]
+    pub fn has<T: 'static>(&self) -> bool {
+        self.has_column(TypeId::of::<T>())
+    }
+
+    /// Returns the column of the given type. There is no mutable version, as changing the length
+    /// of a single column would break the table.
+    #[inline]
+    pub fn erased_column(&self, type_id: TypeId) -> Option<&ErasedVec> {
+        self.column_index(type_id).map(|index| &self.columns[index])
+    }
+
+    /// Returns the column of type `T`, or `None` if there isn't one, or if the column's
+    /// [ErasedType] is not the erased form of `T`
+    #[inline]
+    pub fn column<T: 'static>(&self) -> Option<&[T]> {
+        let column = self.erased_column(TypeId::of::<T>())?;
+        column.as_slice_checked().ok()
+    }
+
+    /// The same as [Table::column], but mutable
+    #[inline]
+    pub fn column_mut<T: 'static>(&mut self) -> Option<&mut [T]> {
+        let index = self.column_index(TypeId::of::<T>())?;
+        self.columns[index].as_slice_mut_checked().ok()
+    }
+
+    #[inline]
+    pub fn get<T: 'static>(&self, row: usize) -> Option<&T> {
+        self.column()?.get(row)
+    }
+
+    #[inline]
+    pub fn get_mut<T: 'static>(&mut self, row: usize) -> Option<&mut T> {
+        self.column_mut()?.get_mut(row)
+    }
+
+    /// Moves a value into each column, as a new row at the end of the table. Returns the index of
+    /// the row.
+    ///
+    /// # Safety
+    /// The caller must ensure that:
+    /// - Each pointer is aligned to, and valid for reading a value of, the type it is paired with.
+    /// - The values are not used or dropped after this, as the table now owns them.
+    ///
+    /// # Panics
+    /// Panics unless there is exactly one value for each column. Nothing is moved if it panics.
+    pub unsafe fn push_row(&mut self, values: &[(TypeId, Ptr)]) -> usize {
+        assert!(
+            values.len() == self.columns.len()
+                && self.type_ids().all(|type_id| count(values, type_id) == 1),
+            "A row must have exactly one value for each column of the table"
+        );
+
+        // reserve everything up front, so a failed allocation can't leave the row half pushed
+        for column in &mut self.columns {
+            column.reserve(1);
+        }
+
+        for &(type_id, value) in values {
+            let index = self.column_index(type_id).unwrap();
+            // SAFETY: The value is of the column's type, as is deferred to the caller
+            unsafe { self.columns[index].push(value) }
+        }
+
+        self.len += 1;
+        self.len - 1
+    }
+
+    /// Removes and drops the row, replacing it with the last row
+    ///
+    /// # Panics
+    /// Panics if `row` is out of bounds
+    pub fn swap_remove_row(&mut self, row: usize) {
+        assert!(
+            row < self.len,
+            "Row index (is {row}) should be < len (is {})",
+            self.len
+        );
+
+        self.len -= 1;
+        let mut guard = RemoveGuard::new(&mut self.columns, row, None);
+        // SAFETY: The row is within the bounds of every column
+        unsafe { guard.remove_remaining() }
+    }
+
+    /// Moves the row into `other`, replacing it with the last row. Values with no column in `other`
+    /// are dropped. Returns the index of the row in `other`.
+    ///
+    /// # Panics
+    /// Panics if `row` is out of bounds, or if `other` has a column that this table doesn't
+    pub fn move_row_to(&mut self, row: usize, other: &mut Table) -> usize {
+        // SAFETY: There are no extra values
+        unsafe { self.move_row_to_with(row, other, &[]) }
+    }
+
+    /// Moves the row into `other`, replacing it with the last row, along with `extra` values for the
+    /// columns of `other` that this table doesn't have. Values with no column in `other` are
+    /// dropped. Returns the index of the row in `other`.
+    ///
+    /// # Safety
+    /// The same as [Table::push_row], for the `extra` values
+    ///
+    /// # Panics
+    /// Panics if `row` is out of bounds, or unless there is exactly one extra value for each column
+    /// that only `other` has. Nothing is moved if it panics.
+    pub unsafe fn move_row_to_with(
+        &mut self,
+        row: usize,
+        other: &mut Table,
+        extra: &[(TypeId, Ptr)],
+    ) -> usize {
+        assert!(
+            row < self.len,
+            "Row index (is {row}) should be < len (is {})",
+            self.len
+        );
+        let missing = || {
+            other
+                .type_ids()
+                .filter(|&type_id| !self.has_column(type_id))
+        };
+        assert!(
+            self.columns.iter().all(|column| {
+                other
+                    .erased_column(column.erased_type().type_id())
+                    .is_none_or(|to| to.erased_type().layout() == column.erased_type().layout())
+            }),
+            "Cannot move a row between columns of the same type with different layouts"
+        );
+        assert!(
+            missing().count() == extra.len()
+                && missing().all(|type_id| count(extra, type_id) == 1),
+            "Moving a row must provide exactly one value for each column that only the other table has"
+        );
+
+        for column in &mut other.columns {
+            column.reserve(1);
+        }
+
+        for column in &mut self.columns {
+            if let Some(index) = other.column_index(column.erased_type().type_id()) {
+                // SAFETY:
+                // - The row is within bounds.
+                // - The value is pushed straight away, so the pointer to the end of the column is
+                //   still valid, and this column no longer owns it.
+                unsafe {
+                    let value = column.swap_remove_unchecked(row);
+                    other.columns[index].push(value);
+                }
+            }
+        }
+
+        for &(type_id, value) in extra {
+            let index = other.column_index(type_id).unwrap();
+            // SAFETY: The value is of the column's type, as is deferred to the caller
+            unsafe { other.columns[index].push(value) }
+        }
+
+        other.len += 1;
+        self.len -= 1;
+
+        // drop the values that `other` has no column for, now that both tables are consistent
+        let mut guard = RemoveGuard::new(&mut self.columns, row, Some(other));
+        // SAFETY: The row is within the bounds of every column that hasn't been moved out of
+        unsafe { guard.remove_remaining() }
+
+        other.len - 1
+    }
+
+    /// Moves the values of a row into a new table with one more column, for when a type is being
+    /// added to the row.
+    ///
+    /// # Panics
+    /// The same as [Table::move_row_to_with]
+    pub fn move_row_to_adding<T: 'static>(
+        &mut self,
+        row: usize,
+        other: &mut Table,
+        value: T,
+    ) -> usize {
+        let mut value = ManuallyDrop::new(value);
+        // SAFETY: The value is of type `T`, and is forgotten here
+        unsafe { self.move_row_to_with(row, other, &[(TypeId::of::<T>(), Ptr::from(&mut *value))]) }
+    }
+
+    #[inline]
+    fn column_index(&self, type_id: TypeId) -> Option<usize> {
+        self.columns
+            .binary_search_by_key(&type_id, |column| column.erased_type().type_id())
+            .ok()
+    }
+}
+
+/// The number of values of the given type
+#[inline]
+fn count(values: &[(TypeId, Ptr)], type_id: TypeId) -> usize {
+    values
+        .iter()
+        .filter(|(value_type, _)| *value_type == type_id)
+        .count()
+}
+
+/// Swap removes a row from each column in turn, and carries on with the remaining columns if a
+/// destructor panics, so that the columns keep the same length
+struct RemoveGuard<'a> {
+    columns: &'a mut [ErasedVec],
+    row: usize,
+    /// The index of the next column to remove from
+    next: usize,
+    /// Columns with a type in this table have already had the row moved out of them
+    skip: Option<&'a Table>,
+}
+
+impl<'a> RemoveGuard<'a> {
+    #[inline]
+    fn new(columns: &'a mut [ErasedVec], row: usize, skip: Option<&'a Table>) -> Self {
+        Self {
+            columns,
+            row,
+            next: 0,
+            skip,
+        }
+    }
+
+    /// # Safety
+    /// The caller must ensure that:
+    /// - The row is within the bounds of every column that isn't skipped.
+    /// - Any existing pointers to the row are not used after this.
+    #[inline]
+    unsafe fn remove_remaining(&mut self) {
+        while self.next < self.columns.len() {
+            let column = &mut self.columns[self.next];
+            // move on before dropping, so a panicking column isn't removed from again
+            self.next += 1;
+
+            let type_id = column.erased_type().type_id();
+            if self.skip.is_some_and(|table| table.has_column(type_id)) {
+                continue;
+            }
+
+            // SAFETY: The row is within bounds, as is deferred to the caller
+            unsafe { column.swap_remove_drop_unchecked(self.row) }
+        }
+    }
+}
+
+impl Drop for RemoveGuard<'_> {
+    fn drop(&mut self) {
+        // only does anything if a destructor panicked part way through
+        // SAFETY: Upheld by the caller of `remove_remaining`
+        unsafe { self.remove_remaining() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::alloc::Layout;
+    use std::{
+        panic::{catch_unwind, AssertUnwindSafe},
+        rc::Rc,
+    };
+
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Position(f32, f32);
+
+    #[derive(Debug, PartialEq)]
+    struct Velocity(f32, f32);
+
+    fn push<A: 'static, B: 'static>(table: &mut Table, a: A, b: B) -> usize {
+        let (mut a, mut b) = (ManuallyDrop::new(a), ManuallyDrop::new(b));
+        unsafe {
+            table.push_row(&[
+                (TypeId::of::<A>(), Ptr::from(&mut *a)),
+                (TypeId::of::<B>(), Ptr::from(&mut *b)),
+            ])
+        }
+    }
+
+    fn names(table: &Table) -> Vec<&str> {
+        table
+            .column::<String>()
+            .unwrap()
+            .iter()
+            .map(String::as_str)
+            .collect()
+    }
+
+    #[test]
+    fn push_and_remove_test() {
+        let mut table = Table::new([ErasedType::new::<String>(), ErasedType::new::<Position>()]);
+        assert!(table.has::<String>() && table.has::<Position>() && !table.has::<Velocity>());
+        assert_eq!(table.column_count(), 2);
+
+        assert_eq!(push(&mut table, String::from("a"), Position(0.0, 0.0)), 0);
+        assert_eq!(push(&mut table, Position(1.0, 1.0), String::from("b")), 1);
+        assert_eq!(push(&mut table, String::from("c"), Position(2.0, 2.0)), 2);
+        assert_eq!(table.len(), 3);
+        assert_eq!(table.get::<Position>(1), Some(&Position(1.0, 1.0)));
+
+        table.get_mut::<Position>(2).unwrap().0 = 5.0;
+        table.column_mut::<String>().unwrap()[0].push('!');
+
+        table.swap_remove_row(0);
+        assert_eq!(names(&table), ["c", "b"]);
+        assert_eq!(
+            table.column::<Position>().unwrap(),
+            [Position(5.0, 2.0), Position(1.0, 1.0)]
+        );
+        assert_eq!(
+            table.erased_column(TypeId::of::<String>()).unwrap().len(),
+            2
+        );
+        assert!(table.column::<Velocity>().is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "A table cannot have two columns of the same type")]
+    fn duplicate_column_test() {
+        Table::new([
+            ErasedType::new::<u8>(),
+            ErasedType::new::<u16>(),
+            ErasedType::new::<u8>(),
+        ]);
+    }
+
+    #[test]
+    fn push_row_mismatch_test() {
+        let mut table = Table::new([ErasedType::new::<u8>(), ErasedType::new::<u16>()]);
+        let (mut a, mut b) = (1u8, 2u8);
+
+        let result = catch_unwind(AssertUnwindSafe(|| unsafe {
+            table.push_row(&[
+                (TypeId::of::<u8>(), Ptr::from(&mut a)),
+                (TypeId::of::<u8>(), Ptr::from(&mut b)),
+            ])
+        }));
+        assert!(result.is_err());
+        assert_eq!(table.len(), 0);
+        assert!(table
+            .type_ids()
+            .all(|type_id| table.erased_column(type_id).unwrap().is_empty()));
+    }
+
+    #[test]
+    fn move_row_test() {
+        let rc = Rc::new(());
+        let mut from = Table::new([ErasedType::new::<String>(), ErasedType::new::<Rc<()>>()]);
+        let mut to = Table::new([ErasedType::new::<String>()]);
+
+        push(&mut from, String::from("a"), rc.clone());
+        push(&mut from, String::from("b"), rc.clone());
+        push(&mut from, String::from("c"), rc.clone());
+        assert_eq!(Rc::strong_count(&rc), 4);
+
+        // the `Rc` column has nowhere to go, so it is dropped
+        assert_eq!(from.move_row_to(0, &mut to), 0);
+        assert_eq!(Rc::strong_count(&rc), 3);
+        assert_eq!(names(&from), ["c", "b"]);
+        assert_eq!(names(&to), ["a"]);
+
+        // and back again, adding the missing column
+        assert_eq!(to.move_row_to_adding(0, &mut from, rc.clone()), 2);
+        assert_eq!(Rc::strong_count(&rc), 4);
+        assert_eq!(names(&from), ["c", "b", "a"]);
+        assert!(to.is_empty());
+
+        drop(from);
+        assert_eq!(Rc::strong_count(&rc), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Moving a row must provide exactly one value for each column")]
+    fn move_row_missing_column_test() {
+        let mut from = Table::new([ErasedType::new::<u8>()]);
+        let mut to = Table::new([ErasedType::new::<u8>(), ErasedType::new::<u16>()]);
+        unsafe { from.push_row(&[(TypeId::of::<u8>(), Ptr::from(&mut 1u8))]) };
+        from.move_row_to(0, &mut to);
+    }
+
+    #[test]
+    fn panicking_drop_test() {
+        struct Panicky;
+
+        impl Drop for Panicky {
+            fn drop(&mut self) {
+                if !std::thread::panicking() {
+                    panic!("Panicky dropped");
+                }
+            }
+        }
+
+        let rc = Rc::new(());
+        let mut table = Table::new([ErasedType::new::<Panicky>(), ErasedType::new::<Rc<()>>()]);
+        push(&mut table, Panicky, rc.clone());
+        push(&mut table, Panicky, rc.clone());
+
+        // whichever column is dropped first, the row is removed from both
+        let result = catch_unwind(AssertUnwindSafe(|| table.swap_remove_row(0)));
+        assert!(result.is_err());
+        assert_eq!(table.len(), 1);
+        assert!(table
+            .type_ids()
+            .all(|type_id| table.erased_column(type_id).unwrap().len() == 1));
+        assert_eq!(Rc::strong_count(&rc), 2);
+
+        // remove the last one in the same way, rather than leaking it, so the test doesn't panic
+        let result = catch_unwind(AssertUnwindSafe(|| table.swap_remove_row(0)));
+        assert!(result.is_err());
+        assert!(table.is_empty());
+        assert_eq!(Rc::strong_count(&rc), 1);
+    }
+
+    #[test]
+    fn forged_column_test() {
+        // a type id paired with a layout too small for it, which breaks the contract of
+        // `from_raw_parts`, but must still never be trusted by the typed accessors
+        let forged = unsafe {
+            ErasedType::from_raw_parts(TypeId::of::<[u64; 8]>(), Layout::new::<u8>(), |_| {})
+        };
+        let mut table = Table::new([forged]);
+        unsafe { table.push_row(&[(TypeId::of::<[u64; 8]>(), Ptr::from(&mut 0u8))]) };
+
+        assert!(table.has::<[u64; 8]>());
+        assert!(table.column::<[u64; 8]>().is_none());
+        assert!(table.column_mut::<[u64; 8]>().is_none());
+        assert!(table.get::<[u64; 8]>(0).is_none());
+        assert!(table.get_mut::<[u64; 8]>(0).is_none());
+
+        let mut real = Table::new([ErasedType::new::<[u64; 8]>()]);
+        let result = catch_unwind(AssertUnwindSafe(|| table.move_row_to(0, &mut real)));
+        assert!(result.is_err());
+        assert_eq!(table.len(), 1);
+        assert!(real.is_empty());
+    }
+}